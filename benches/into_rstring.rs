@@ -0,0 +1,35 @@
+use std::time::Instant;
+
+use redrs::{RedString, RedStringBuilder};
+
+const ITERATIONS: u32 = 100_000;
+
+fn bench(name: &str, len: usize, mut f: impl FnMut() -> magnus::RString) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(f());
+    }
+    let per_iter = start.elapsed() / ITERATIONS;
+    println!(
+        "{name:<24} {len:>6} bytes {:>8} ns/iter",
+        per_iter.as_nanos()
+    );
+}
+
+fn main() {
+    rb_sys_test_helpers::with_ruby_vm(|| {
+        for len in [8, 23, 24, 64, 1024] {
+            let text = "x".repeat(len);
+            bench("RedString::into_rstring", len, || {
+                RedString::from_str(&text).into_rstring()
+            });
+            bench("RedStringBuilder", len, || {
+                let mut b = RedStringBuilder::new();
+                b.push_str(&text[..len / 2]);
+                b.push_str(&text[len / 2..]);
+                b.into_rstring()
+            });
+        }
+    })
+    .unwrap();
+}
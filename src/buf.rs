@@ -0,0 +1,118 @@
+use bytes::{buf::UninitSlice, Buf, BufMut};
+
+use crate::RedBytes;
+
+impl RedBytes {
+    // Reads from the front by moving a position instead of shifting the
+    // remaining bytes down, so `advance` is O(1) as decoders expect.
+    pub fn into_buf(self) -> RedBytesReader {
+        RedBytesReader {
+            bytes: self,
+            pos: 0,
+        }
+    }
+}
+
+pub struct RedBytesReader {
+    bytes: RedBytes,
+    pos: usize,
+}
+
+impl RedBytesReader {
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    // All of the bytes, including the ones already read.
+    pub fn into_inner(self) -> RedBytes {
+        self.bytes
+    }
+}
+
+impl Buf for RedBytesReader {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.bytes[self.pos..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.remaining(),
+            "cannot advance past `remaining`: {} <= {}",
+            cnt,
+            self.remaining()
+        );
+        self.pos += cnt;
+    }
+}
+
+unsafe impl BufMut for RedBytes {
+    fn remaining_mut(&self) -> usize {
+        isize::MAX as usize - self.len()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        let len = self.len();
+        let spare = self.capacity() - len;
+        assert!(
+            cnt <= spare,
+            "cannot advance past `remaining_mut`: {} <= {}",
+            cnt,
+            spare
+        );
+        self.buf.set_len(len + cnt);
+    }
+
+    // Same growth step as `Vec<u8>`'s impl when the buffer is full.
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        if self.capacity() == self.len() {
+            self.reserve(64);
+        }
+        UninitSlice::uninit(self.buf.spare_capacity_mut())
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        self.extend_from_slice(src);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Buf, BufMut};
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedBytes;
+
+    fn encode(dst: &mut impl BufMut) {
+        dst.put_u16(0x0102);
+        dst.put_slice(b"payload");
+    }
+
+    #[ruby_test]
+    fn test_buf_mut() {
+        let mut b = RedBytes::new();
+        encode(&mut b);
+        assert_eq!(b.as_slice(), b"\x01\x02payload");
+
+        let mut full = RedBytes::with_capacity(0);
+        unsafe {
+            full.chunk_mut().as_mut_ptr().write(b'x');
+            full.advance_mut(1);
+        }
+        assert_eq!(full.as_slice(), b"x");
+    }
+
+    #[ruby_test]
+    fn test_buf() {
+        let mut b = RedBytes::from_slice(b"\x01\x02rest").into_buf();
+        assert_eq!(b.get_u16(), 0x0102);
+        assert_eq!(b.remaining(), 4);
+        b.advance(1);
+        assert_eq!(b.chunk(), b"est");
+        assert_eq!(b.position(), 3);
+        assert_eq!(b.into_inner().len(), 6);
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(feature = "magnus")]
+use magnus::{prelude::*, rb_sys::FromRawValue};
+
+use crate::{RedString, RedVec, RubyAllocator, EMBED_LEN_MAX};
+
+const MIN_CHUNK: usize = 256;
+const MAX_CHUNK: usize = 64 * 1024;
+
+enum Segment<'a> {
+    Owned(RedString),
+    #[cfg(feature = "magnus")]
+    Borrowed(&'a magnus::RString),
+    #[cfg(not(feature = "magnus"))]
+    _Borrowed(std::marker::PhantomData<&'a ()>),
+}
+
+impl Segment<'_> {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Segment::Owned(chunk) => chunk.as_bytes(),
+            #[cfg(feature = "magnus")]
+            Segment::Borrowed(s) => unsafe { s.as_slice() },
+            #[cfg(not(feature = "magnus"))]
+            Segment::_Borrowed(_) => unreachable!(),
+        }
+    }
+}
+
+// Collects pieces into a list of chunks instead of one contiguous buffer, so
+// appending never reallocates and copies what was already written. The final
+// string is gathered with a single allocation of the exact total size.
+pub struct RedStringBuilder<'a> {
+    segments: RedVec<Segment<'a>>,
+    len: usize,
+    next_chunk: usize,
+}
+
+impl<'a> RedStringBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            segments: RedVec::new_in(RubyAllocator::new()),
+            len: 0,
+            next_chunk: MIN_CHUNK,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        self.chunk_for(s.len()).push_str(s);
+        self.len += s.len();
+    }
+
+    // Frozen strings are referenced rather than copied; their bytes are only
+    // read when the result is gathered. Anything else is copied right away
+    // since the caller could still modify it. A builder kept off the stack
+    // should borrow from a `Pinned` handle, so the string can't move first.
+    #[cfg(feature = "magnus")]
+    pub fn push_rstring(&mut self, s: &'a magnus::RString) -> Result<(), magnus::Error> {
+        let str = unsafe { s.as_str()? };
+        if s.is_frozen() {
+            self.len += str.len();
+            self.segments.push(Segment::Borrowed(s));
+        } else {
+            self.push_str(str);
+        }
+        Ok(())
+    }
+
+    // Short results are gathered on the stack and handed to Ruby in one call,
+    // which stores them inside the string object itself.
+    pub fn into_raw_rstring(self) -> rb_sys::VALUE {
+        if self.len <= EMBED_LEN_MAX {
+            let mut buf = [0; EMBED_LEN_MAX];
+            let mut len = 0;
+            for segment in self.segments.iter() {
+                let bytes = segment.as_bytes();
+                buf[len..len + bytes.len()].copy_from_slice(bytes);
+                len += bytes.len();
+            }
+            unsafe {
+                let raw_value = rb_sys::rb_utf8_str_new(buf.as_ptr() as *const i8, len as _);
+                crate::set_coderange(raw_value, buf[..len].is_ascii());
+                return raw_value;
+            }
+        }
+
+        unsafe {
+            let raw_value = rb_sys::rb_str_buf_new(self.len.try_into().unwrap());
+            let mut ascii = true;
+            for segment in self.segments.iter() {
+                let bytes = segment.as_bytes();
+                ascii &= bytes.is_ascii();
+                rb_sys::rb_str_cat(
+                    raw_value,
+                    bytes.as_ptr() as *const i8,
+                    bytes.len().try_into().unwrap(),
+                );
+            }
+            rb_sys::rb_enc_associate_index(raw_value, rb_sys::rb_utf8_encindex());
+            crate::set_coderange(raw_value, ascii);
+            raw_value
+        }
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        let raw_value = self.into_raw_rstring();
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+
+    fn chunk_for(&mut self, additional: usize) -> &mut RedString {
+        let fits = match self.segments.last() {
+            Some(Segment::Owned(chunk)) => chunk.capacity() - chunk.len() >= additional,
+            _ => false,
+        };
+
+        if !fits {
+            let capacity = self.next_chunk.max(additional);
+            self.next_chunk = (self.next_chunk * 2).min(MAX_CHUNK);
+            self.segments
+                .push(Segment::Owned(RedString::with_capacity(capacity)));
+        }
+
+        match self.segments.last_mut() {
+            Some(Segment::Owned(chunk)) => chunk,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Default for RedStringBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Write for RedStringBuilder<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "magnus"))]
+mod tests {
+    use magnus::prelude::*;
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RedStringBuilder;
+
+    #[ruby_test]
+    fn test_push_str() {
+        let mut b = RedStringBuilder::new();
+        b.push_str("abc");
+        b.push('d');
+        assert_eq!(b.len(), 4);
+        assert_eq!(b.into_rstring().to_string().unwrap(), "abcd");
+    }
+
+    #[ruby_test]
+    fn test_many_chunks() {
+        let mut b = RedStringBuilder::new();
+        for _ in 0..1000 {
+            b.push_str("0123456789");
+        }
+        assert_eq!(b.len(), 10_000);
+        assert_eq!(
+            b.into_rstring().to_string().unwrap(),
+            "0123456789".repeat(1000)
+        );
+    }
+
+    #[ruby_test]
+    fn test_push_rstring() {
+        let frozen = magnus::RString::new("frozen");
+        frozen.freeze();
+        let mutable = magnus::RString::new("mutable");
+
+        let mut b = RedStringBuilder::new();
+        b.push_str("[");
+        b.push_rstring(&frozen).unwrap();
+        b.push_str(",");
+        b.push_rstring(&mutable).unwrap();
+        b.push_str("]");
+        assert_eq!(b.into_rstring().to_string().unwrap(), "[frozen,mutable]");
+    }
+
+    #[ruby_test]
+    fn test_embed_threshold() {
+        for len in [crate::EMBED_LEN_MAX, crate::EMBED_LEN_MAX + 1] {
+            let mut b = RedStringBuilder::new();
+            b.push_str(&"é".repeat(len / 2));
+            b.push_str(&"x".repeat(len % 2));
+            let expected = "é".repeat(len / 2) + &"x".repeat(len % 2);
+            assert_eq!(b.into_rstring().to_string().unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::cmp::Ordering;
+
+use crate::RedString;
+
+impl RedString {
+    // Like `String#casecmp`: only ASCII letters are folded, and everything
+    // else is compared byte by byte.
+    pub fn casecmp(&self, other: &str) -> Ordering {
+        let fold = |b: &u8| b.to_ascii_lowercase();
+        self.as_bytes()
+            .iter()
+            .map(fold)
+            .cmp(other.as_bytes().iter().map(fold))
+    }
+
+    // Like `String#casecmp?`: full Unicode case folding, so "Straße" equals
+    // "STRASSE". Neither side is normalized first, same as in Ruby.
+    #[cfg(feature = "unicode")]
+    pub fn casecmp_eq(&self, other: &str) -> bool {
+        use caseless::Caseless;
+
+        self.chars()
+            .default_case_fold()
+            .eq(other.chars().default_case_fold())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_casecmp() {
+        let s = RedString::from_str("Content-Type");
+        assert_eq!(s.casecmp("content-type"), Ordering::Equal);
+        assert_eq!(s.casecmp("CONTENT-TYPF"), Ordering::Less);
+        assert_eq!(s.casecmp("Content"), Ordering::Greater);
+        assert_eq!(RedString::from_str("_").casecmp("A"), Ordering::Less);
+        assert_eq!(RedString::from_str("É").casecmp("é"), Ordering::Less);
+    }
+
+    #[cfg(feature = "unicode")]
+    #[ruby_test]
+    fn test_casecmp_eq() {
+        assert!(RedString::from_str("Straße").casecmp_eq("STRASSE"));
+        assert!(RedString::from_str("ÉTÉ").casecmp_eq("été"));
+        assert!(!RedString::from_str("e\u{301}").casecmp_eq("é"));
+        assert!(!RedString::from_str("abc").casecmp_eq("abd"));
+    }
+}
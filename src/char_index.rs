@@ -0,0 +1,131 @@
+use crate::{RedString, RedVec, RubyAllocator};
+
+// Byte offset of every STRIDE-th char, so a lookup scans at most STRIDE chars.
+const STRIDE: usize = 64;
+
+// Maps between char offsets (what Ruby reports) and byte offsets into a
+// string. Borrowing the string keeps the index from going stale.
+pub struct CharIndex<'a> {
+    s: &'a str,
+    samples: RedVec<usize>,
+    chars: usize,
+}
+
+impl<'a> CharIndex<'a> {
+    pub fn new(s: &'a str) -> Self {
+        let mut samples = RedVec::new_in(RubyAllocator::new());
+        // ASCII needs no samples, every char is one byte
+        if s.is_ascii() {
+            return Self {
+                s,
+                samples,
+                chars: s.len(),
+            };
+        }
+
+        let mut chars = 0;
+        for (i, (idx, _)) in s.char_indices().enumerate() {
+            if i % STRIDE == 0 {
+                samples.push(idx);
+            }
+            chars += 1;
+        }
+        Self { s, samples, chars }
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.chars
+    }
+
+    // `None` past the end; the char count itself maps to the byte length.
+    pub fn char_to_byte(&self, char_idx: usize) -> Option<usize> {
+        if char_idx > self.chars {
+            return None;
+        }
+        if self.samples.is_empty() {
+            return Some(char_idx);
+        }
+        if char_idx == self.chars {
+            return Some(self.s.len());
+        }
+        let start = self.samples[char_idx / STRIDE];
+        let (idx, _) = self.s[start..].char_indices().nth(char_idx % STRIDE)?;
+        Some(start + idx)
+    }
+
+    // `None` if `byte_idx` is out of bounds or not on a char boundary.
+    pub fn byte_to_char(&self, byte_idx: usize) -> Option<usize> {
+        if !self.s.is_char_boundary(byte_idx) {
+            return None;
+        }
+        if self.samples.is_empty() {
+            return Some(byte_idx);
+        }
+        let sample = self.samples.partition_point(|&idx| idx <= byte_idx) - 1;
+        let start = self.samples[sample];
+        Some(sample * STRIDE + self.s[start..byte_idx].chars().count())
+    }
+}
+
+impl RedString {
+    pub fn char_index(&self) -> CharIndex<'_> {
+        CharIndex::new(self)
+    }
+
+    // One-off conversions; build a `CharIndex` when doing many.
+    pub fn char_to_byte_idx(&self, char_idx: usize) -> Option<usize> {
+        self.char_indices()
+            .map(|(idx, _)| idx)
+            .chain(std::iter::once(self.len()))
+            .nth(char_idx)
+    }
+
+    pub fn byte_to_char_idx(&self, byte_idx: usize) -> Option<usize> {
+        if !self.is_char_boundary(byte_idx) {
+            return None;
+        }
+        Some(self[..byte_idx].chars().count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_char_index() {
+        let s = RedString::repeat("aé日🎉", 32);
+        let index = s.char_index();
+        assert_eq!(index.char_count(), 128);
+
+        for (char_idx, (byte_idx, _)) in s.char_indices().enumerate() {
+            assert_eq!(index.char_to_byte(char_idx), Some(byte_idx));
+            assert_eq!(index.byte_to_char(byte_idx), Some(char_idx));
+        }
+        assert_eq!(index.char_to_byte(128), Some(s.len()));
+        assert_eq!(index.byte_to_char(s.len()), Some(128));
+        assert_eq!(index.char_to_byte(129), None);
+        assert_eq!(index.byte_to_char(2), None);
+    }
+
+    #[ruby_test]
+    fn test_char_index_ascii() {
+        let s = RedString::from_str("hello");
+        let index = s.char_index();
+        assert_eq!(index.char_to_byte(3), Some(3));
+        assert_eq!(index.byte_to_char(5), Some(5));
+        assert_eq!(index.char_to_byte(6), None);
+    }
+
+    #[ruby_test]
+    fn test_char_to_byte_idx() {
+        let s = RedString::from_str("héllo");
+        assert_eq!(s.char_to_byte_idx(2), Some(3));
+        assert_eq!(s.char_to_byte_idx(5), Some(6));
+        assert_eq!(s.char_to_byte_idx(6), None);
+        assert_eq!(s.byte_to_char_idx(3), Some(2));
+        assert_eq!(s.byte_to_char_idx(2), None);
+    }
+}
@@ -0,0 +1,268 @@
+use std::ops::RangeBounds;
+
+use crate::RedString;
+
+impl RedString {
+    pub fn into_chars(self) -> RedChars {
+        RedChars {
+            front: 0,
+            back: self.len(),
+            string: self,
+        }
+    }
+
+    // Removes `range` once the iterator is dropped, whether or not it was
+    // run to the end. Panics if `range` is out of bounds or doesn't fall on
+    // char boundaries.
+    pub fn drain<R>(&mut self, range: R) -> RedDrain<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.resolve_char_range(range);
+        RedDrain {
+            string: self,
+            start,
+            end,
+            front: start,
+            back: end,
+        }
+    }
+
+    // Walks the string one char at a time, allowing the char just returned
+    // to be replaced or removed, or text to be inserted, without restarting.
+    pub fn char_indices_mut(&mut self) -> CharIndicesMut<'_> {
+        CharIndicesMut {
+            string: self,
+            pos: 0,
+            last: None,
+        }
+    }
+}
+
+// Byte positions are relative to the start of the string being consumed.
+pub struct RedChars {
+    string: RedString,
+    front: usize,
+    back: usize,
+}
+
+impl RedChars {
+    // Byte offset of the next char from the front.
+    pub fn position(&self) -> usize {
+        self.front
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.string[self.front..self.back]
+    }
+}
+
+impl Iterator for RedChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next()?;
+        self.front += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+impl DoubleEndedIterator for RedChars {
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.back -= ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl std::iter::FusedIterator for RedChars {}
+
+pub struct RedDrain<'a> {
+    string: &'a mut RedString,
+    start: usize,
+    end: usize,
+    front: usize,
+    back: usize,
+}
+
+impl RedDrain<'_> {
+    // Byte offset of the next char from the front, in the string as it was
+    // before draining.
+    pub fn position(&self) -> usize {
+        self.front
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.string[self.front..self.back]
+    }
+}
+
+impl Iterator for RedDrain<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next()?;
+        self.front += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+impl DoubleEndedIterator for RedDrain<'_> {
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.back -= ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl std::iter::FusedIterator for RedDrain<'_> {}
+
+impl Drop for RedDrain<'_> {
+    fn drop(&mut self) {
+        self.string.splice(self.start..self.end, "");
+    }
+}
+
+// Not an `Iterator`, since a `for` loop would hold the borrow needed to edit:
+//
+//     let mut chars = s.char_indices_mut();
+//     while let Some((_, ch)) = chars.next() {
+//         if ch == '&' {
+//             chars.replace("&amp;");
+//         }
+//     }
+//
+// Positions are byte offsets into the string as edited so far.
+pub struct CharIndicesMut<'a> {
+    string: &'a mut RedString,
+    pos: usize,
+    // Start and length of the char returned by the last `next`, until it is
+    // edited.
+    last: Option<(usize, usize)>,
+}
+
+impl CharIndicesMut<'_> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(usize, char)> {
+        let ch = self.string[self.pos..].chars().next()?;
+        let start = self.pos;
+        self.pos += ch.len_utf8();
+        self.last = Some((start, ch.len_utf8()));
+        Some((start, ch))
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.string[self.pos..].chars().next()
+    }
+
+    // Byte offset of the next char.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.string.as_str()
+    }
+
+    // Replaces the char returned by the last `next`; iteration continues
+    // after the replacement. Panics if there is none, or it was already
+    // edited.
+    pub fn replace(&mut self, replacement: &str) {
+        let (start, len) = self.take_last();
+        self.string.splice(start..start + len, replacement);
+        self.pos = start + replacement.len();
+    }
+
+    // Removes the char returned by the last `next`, with the same panics as
+    // `replace`.
+    pub fn remove(&mut self) -> char {
+        let (start, _) = self.take_last();
+        self.pos = start;
+        self.string.remove(start)
+    }
+
+    // Inserts `s` before the next char and continues after it.
+    pub fn insert_str(&mut self, s: &str) {
+        self.string.insert_str(self.pos, s);
+        self.pos += s.len();
+        self.last = None;
+    }
+
+    fn take_last(&mut self) -> (usize, usize) {
+        self.last
+            .take()
+            .expect("no char to edit: call `next` first")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_into_chars() {
+        let mut chars = RedString::from_str("aé€z").into_chars();
+        assert_eq!(chars.next(), Some('a'));
+        assert_eq!(chars.position(), 1);
+        assert_eq!(chars.next_back(), Some('z'));
+        assert_eq!(chars.as_str(), "é€");
+        assert_eq!(chars.collect::<String>(), "é€");
+    }
+
+    #[ruby_test]
+    fn test_drain() {
+        let mut s = RedString::from_str("héllo world");
+        let mut drain = s.drain(1..7);
+        assert_eq!(drain.next(), Some('é'));
+        assert_eq!(drain.position(), 3);
+        assert_eq!(drain.next_back(), Some(' '));
+        drop(drain);
+        assert_eq!(s.as_str(), "hworld");
+
+        let drained: String = s.drain(..).collect();
+        assert_eq!(drained, "hworld");
+        assert!(s.is_empty());
+    }
+
+    #[ruby_test]
+    fn test_char_indices_mut() {
+        let mut s = RedString::from_str("a&b<c");
+        let mut chars = s.char_indices_mut();
+        let mut seen = Vec::new();
+        while let Some((pos, ch)) = chars.next() {
+            seen.push((pos, ch));
+            match ch {
+                '&' => chars.replace("&amp;"),
+                '<' => {
+                    chars.remove();
+                }
+                'b' => chars.insert_str("!"),
+                _ => {}
+            }
+        }
+        assert_eq!(seen, [(0, 'a'), (1, '&'), (6, 'b'), (8, '<'), (8, 'c')]);
+        assert_eq!(s.as_str(), "a&amp;b!c");
+    }
+
+    #[ruby_test]
+    #[should_panic]
+    fn test_char_indices_mut_edit_twice() {
+        let mut s = RedString::from_str("ab");
+        let mut chars = s.char_indices_mut();
+        chars.next();
+        chars.remove();
+        chars.remove();
+    }
+}
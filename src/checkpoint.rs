@@ -0,0 +1,143 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::RedString;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mark(usize);
+
+impl Mark {
+    pub fn offset(self) -> usize {
+        self.0
+    }
+}
+
+impl RedString {
+    pub fn checkpoint(&self) -> Mark {
+        Mark(self.len())
+    }
+
+    // Panics if the string has already been truncated below the mark, which
+    // means marks were rolled back out of order.
+    pub fn rollback_to(&mut self, mark: Mark) {
+        assert!(
+            mark.0 <= self.len(),
+            "rollback to {} past the end of a string of length {}",
+            mark.0,
+            self.len()
+        );
+        assert!(self.is_char_boundary(mark.0));
+        self.buf.truncate(mark.0);
+    }
+
+    pub fn speculate(&mut self) -> Checkpoint<'_> {
+        Checkpoint {
+            mark: self.checkpoint(),
+            string: self,
+            committed: false,
+        }
+    }
+}
+
+// Rolls back everything written through it when dropped, unless committed.
+// Nested checkpoints borrow the outer one, so they are always resolved
+// innermost first.
+pub struct Checkpoint<'a> {
+    string: &'a mut RedString,
+    mark: Mark,
+    committed: bool,
+}
+
+impl Checkpoint<'_> {
+    // Empty if the string was cut back below the mark through this guard.
+    pub fn written(&self) -> &str {
+        self.string.get(self.mark.0..).unwrap_or("")
+    }
+
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    pub fn commit_if_written(self) -> bool {
+        let written = !self.written().is_empty();
+        if written {
+            self.commit();
+        }
+        written
+    }
+}
+
+impl Deref for Checkpoint<'_> {
+    type Target = RedString;
+
+    fn deref(&self) -> &Self::Target {
+        self.string
+    }
+}
+
+impl DerefMut for Checkpoint<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.string
+    }
+}
+
+// Code inside the speculation may have cut the string back below the mark
+// already, in which case there is nothing left to roll back. Panicking here
+// would abort if the thread is already unwinding.
+impl Drop for Checkpoint<'_> {
+    fn drop(&mut self) {
+        let mark = self.mark.0;
+        if !self.committed && self.string.len() > mark && self.string.is_char_boundary(mark) {
+            self.string.buf.truncate(mark);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_rollback_to() {
+        let mut s = RedString::from_str("head");
+        let mark = s.checkpoint();
+        s.push_str(" tail");
+        s.rollback_to(mark);
+        assert_eq!(s.as_str(), "head");
+    }
+
+    #[ruby_test]
+    fn test_speculate() {
+        let mut s = RedString::from_str("<ul>");
+        {
+            let mut outer = s.speculate();
+            outer.push_str("<li>");
+            {
+                let mut inner = outer.speculate();
+                inner.push_str("dropped");
+            }
+            outer.push_str("kept</li>");
+            outer.commit();
+        }
+        {
+            let mut empty = s.speculate();
+            empty.push_str("");
+            assert!(!empty.commit_if_written());
+        }
+        s.push_str("</ul>");
+        assert_eq!(s.as_str(), "<ul><li>kept</li></ul>");
+    }
+
+    #[ruby_test]
+    fn test_speculate_truncated_below_mark() {
+        let mut s = RedString::from_str("prefix");
+        {
+            let mut cp = s.speculate();
+            cp.clear();
+            cp.push_str("ab");
+            assert_eq!(cp.written(), "");
+        }
+        assert_eq!(s.as_str(), "ab");
+    }
+}
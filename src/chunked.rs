@@ -0,0 +1,193 @@
+use magnus::{
+    prelude::*,
+    rb_sys::{protect, FromRawValue},
+};
+
+use crate::{RedBytes, RedString};
+
+impl RedString {
+    // Calls `block` with the contents `chunk_size` bytes at a time, so a Ruby
+    // consumer like `Digest#update` or `IO#write` can process them without
+    // the whole thing ever being one Ruby string. Chunks are UTF-8 strings
+    // that end on a char boundary, so some can be up to 3 bytes short.
+    pub fn for_each_chunk(
+        &self,
+        chunk_size: usize,
+        block: magnus::Value,
+    ) -> Result<(), magnus::Error> {
+        each_chunk(self.as_bytes(), chunk_size, true, false, block)
+    }
+
+    // Like `for_each_chunk`, but every call gets the same string, refilled
+    // in place. Cheaper, but the block must not keep the string or modify
+    // it; copy it with `dup` if it needs to outlive the call.
+    pub fn for_each_chunk_reusing(
+        &self,
+        chunk_size: usize,
+        block: magnus::Value,
+    ) -> Result<(), magnus::Error> {
+        each_chunk(self.as_bytes(), chunk_size, true, true, block)
+    }
+}
+
+impl RedBytes {
+    // Chunks are binary strings of exactly `chunk_size` bytes, apart from
+    // the last one.
+    pub fn for_each_chunk(
+        &self,
+        chunk_size: usize,
+        block: magnus::Value,
+    ) -> Result<(), magnus::Error> {
+        each_chunk(self.as_slice(), chunk_size, false, false, block)
+    }
+
+    pub fn for_each_chunk_reusing(
+        &self,
+        chunk_size: usize,
+        block: magnus::Value,
+    ) -> Result<(), magnus::Error> {
+        each_chunk(self.as_slice(), chunk_size, false, true, block)
+    }
+}
+
+fn each_chunk(
+    bytes: &[u8],
+    chunk_size: usize,
+    utf8: bool,
+    reuse: bool,
+    block: magnus::Value,
+) -> Result<(), magnus::Error> {
+    assert!(chunk_size >= 4, "chunk size must be at least 4 bytes");
+    // Held on the stack for the whole loop, so the GC can see it.
+    let reused = reuse.then(|| new_string(&[], chunk_size, utf8));
+
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let mut len = chunk_size.min(rest.len());
+        if utf8 {
+            // Back off to the start of a char cut by the chunk end.
+            while len < rest.len() && (rest[len] & 0xc0) == 0x80 {
+                len -= 1;
+            }
+        }
+        let (chunk, tail) = rest.split_at(len);
+        let arg = match reused {
+            Some(raw) => {
+                refill(raw, chunk, utf8)?;
+                raw
+            }
+            None => new_string(chunk, chunk.len(), utf8),
+        };
+        let _: magnus::Value = block.funcall("call", (unsafe { magnus::Value::from_raw(arg) },))?;
+        rest = tail;
+    }
+    Ok(())
+}
+
+fn new_string(bytes: &[u8], capacity: usize, utf8: bool) -> rb_sys::VALUE {
+    unsafe {
+        let raw = rb_sys::rb_str_buf_new(capacity.try_into().unwrap());
+        rb_sys::rb_str_cat(
+            raw,
+            bytes.as_ptr() as *const i8,
+            bytes.len().try_into().unwrap(),
+        );
+        if utf8 {
+            rb_sys::rb_enc_associate_index(raw, rb_sys::rb_utf8_encindex());
+            crate::set_coderange(raw, bytes.is_ascii());
+        }
+        raw
+    }
+}
+
+// Unshares the buffer first, in case the block kept a `dup`. Fails, rather
+// than writing, if the block froze the string.
+fn refill(raw: rb_sys::VALUE, bytes: &[u8], utf8: bool) -> Result<(), magnus::Error> {
+    protect(|| unsafe {
+        rb_sys::rb_str_modify(raw);
+        rb_sys::rb_str_set_len(raw, 0);
+        rb_sys::rb_str_cat(
+            raw,
+            bytes.as_ptr() as *const i8,
+            bytes.len().try_into().unwrap(),
+        );
+        if utf8 {
+            crate::set_coderange(raw, bytes.is_ascii());
+        }
+        raw
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::{eval, prelude::*, RArray, RString, Value};
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::{RedBytes, RedString};
+
+    #[ruby_test]
+    fn test_for_each_chunk() {
+        let chunks = RArray::new();
+        let block: Value =
+            eval::<Value>("->(chunks) { proc { |chunk| chunks << [chunk.encoding.to_s, chunk] } }")
+                .unwrap()
+                .funcall("call", (chunks,))
+                .unwrap();
+
+        RedString::from_str("abcdéf")
+            .for_each_chunk(5, block)
+            .unwrap();
+        let got: Vec<(String, String)> = chunks.to_vec().unwrap();
+        assert_eq!(
+            got,
+            [
+                ("UTF-8".to_owned(), "abcd".to_owned()),
+                ("UTF-8".to_owned(), "éf".to_owned())
+            ]
+        );
+
+        chunks.clear().unwrap();
+        RedBytes::from_slice(b"abcdefghij")
+            .for_each_chunk(4, block)
+            .unwrap();
+        let got: Vec<(String, String)> = chunks.to_vec().unwrap();
+        let got: Vec<_> = got
+            .iter()
+            .map(|(enc, s)| (enc.as_str(), s.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("ASCII-8BIT", "abcd"),
+                ("ASCII-8BIT", "efgh"),
+                ("ASCII-8BIT", "ij")
+            ]
+        );
+    }
+
+    #[ruby_test]
+    fn test_for_each_chunk_reusing() {
+        let ids = RArray::new();
+        let digest = RString::new("");
+        let block: Value = eval::<Value>(
+            "->(ids, digest) { proc { |chunk| ids << chunk.object_id; digest << chunk } }",
+        )
+        .unwrap()
+        .funcall("call", (ids, digest))
+        .unwrap();
+        RedString::from_str(&"xyz".repeat(10))
+            .for_each_chunk_reusing(7, block)
+            .unwrap();
+
+        assert_eq!(digest.to_string().unwrap(), "xyz".repeat(10));
+        let mut ids: Vec<i64> = ids.to_vec().unwrap();
+        ids.dedup();
+        assert_eq!(ids.len(), 1);
+
+        let freezer = eval("proc { |chunk| chunk.freeze }").unwrap();
+        assert!(RedBytes::from_slice(b"0123456789")
+            .for_each_chunk_reusing(4, freezer)
+            .is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::ops::Deref;
+
+#[cfg(feature = "magnus")]
+use magnus::{prelude::*, rb_sys::FromRawValue};
+
+use crate::RedString;
+
+// Lets transforms that usually leave their input alone return it without
+// allocating, and only copy into Ruby's heap once something changes.
+pub enum RedCow<'a> {
+    Borrowed(&'a str),
+    Owned(RedString),
+    #[cfg(feature = "magnus")]
+    Frozen(FrozenRString<'a>),
+}
+
+// A frozen, valid UTF-8 Ruby string; only `RedCow::from_rstring` makes these,
+// so its contents can be handed out as `&str` for as long as it is borrowed.
+#[cfg(feature = "magnus")]
+pub struct FrozenRString<'a> {
+    rstring: &'a magnus::RString,
+    s: &'a str,
+}
+
+impl<'a> RedCow<'a> {
+    // Frozen UTF-8 strings are borrowed, anything else is copied since Ruby
+    // could change it underneath us.
+    #[cfg(feature = "magnus")]
+    pub fn from_rstring(rstring: &'a magnus::RString) -> Result<Self, magnus::Error> {
+        let s = unsafe { rstring.as_str()? };
+        if rstring.is_frozen() {
+            Ok(Self::Frozen(FrozenRString { rstring, s }))
+        } else {
+            Ok(Self::Owned(RedString::from_str(s)))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Borrowed(s) => s,
+            Self::Owned(s) => s.as_str(),
+            #[cfg(feature = "magnus")]
+            Self::Frozen(frozen) => frozen.s,
+        }
+    }
+
+    pub fn is_owned(&self) -> bool {
+        matches!(self, Self::Owned(_))
+    }
+
+    pub fn to_mut(&mut self) -> &mut RedString {
+        if !self.is_owned() {
+            *self = Self::Owned(RedString::from_str(self.as_str()));
+        }
+        match self {
+            Self::Owned(s) => s,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn into_owned(self) -> RedString {
+        match self {
+            Self::Owned(s) => s,
+            cow => RedString::from_str(cow.as_str()),
+        }
+    }
+
+    // A frozen string goes back as is, and borrowed text is copied straight
+    // into the new Ruby string without an intermediate buffer.
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        match self {
+            Self::Borrowed(s) => {
+                let raw_value = unsafe {
+                    let raw_value = rb_sys::rb_utf8_str_new(
+                        s.as_ptr() as *const i8,
+                        s.len().try_into().unwrap(),
+                    );
+                    crate::set_coderange(raw_value, s.is_ascii());
+                    raw_value
+                };
+                magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+            }
+            Self::Owned(s) => s.into_rstring(),
+            Self::Frozen(frozen) => *frozen.rstring,
+        }
+    }
+
+    // Straight from the borrowed bytes, so splitting a frozen Ruby string
+    // copies each field once and nothing else.
+    #[cfg(feature = "magnus")]
+    pub fn split_to_rarray(&self, pattern: &str) -> magnus::RArray {
+        crate::rstring::split_to_rarray(self.as_str(), pattern)
+    }
+}
+
+impl Deref for RedCow<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<'a> From<&'a str> for RedCow<'a> {
+    fn from(s: &'a str) -> Self {
+        Self::Borrowed(s)
+    }
+}
+
+impl From<RedString> for RedCow<'_> {
+    fn from(s: RedString) -> Self {
+        Self::Owned(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RedCow;
+
+    fn escape_ampersands(s: &str) -> RedCow<'_> {
+        let mut cow = RedCow::from(s);
+        if s.contains('&') {
+            cow.to_mut().replace_in_place("&", "&amp;");
+        }
+        cow
+    }
+
+    #[ruby_test]
+    fn test_to_mut() {
+        let unchanged = escape_ampersands("plain");
+        assert!(!unchanged.is_owned());
+        assert_eq!(&*unchanged, "plain");
+
+        let changed = escape_ampersands("a & b");
+        assert!(changed.is_owned());
+        assert_eq!(changed.into_owned().as_str(), "a &amp; b");
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_from_rstring() {
+        use magnus::{prelude::*, rb_sys::AsRawValue};
+
+        let frozen = magnus::RString::new("frozen");
+        frozen.freeze();
+        let cow = RedCow::from_rstring(&frozen).unwrap();
+        assert!(matches!(cow, RedCow::Frozen(_)));
+        assert_eq!(cow.into_rstring().as_raw(), frozen.as_raw());
+
+        let mutable = magnus::RString::new("mutable");
+        let cow = RedCow::from_rstring(&mutable).unwrap();
+        assert!(cow.is_owned());
+        assert_eq!(cow.into_rstring().to_string().unwrap(), "mutable");
+
+        let borrowed = RedCow::from("borrowed").into_rstring();
+        assert_eq!(borrowed.to_string().unwrap(), "borrowed");
+
+        let csv = magnus::RString::new("a,b,,");
+        csv.freeze();
+        let fields: Vec<String> = RedCow::from_rstring(&csv)
+            .unwrap()
+            .split_to_rarray(",")
+            .to_vec()
+            .unwrap();
+        assert_eq!(fields, ["a", "b"]);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::RedString;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Terminator {
+    Lf,
+    CrLf,
+}
+
+impl Terminator {
+    fn as_str(self) -> &'static str {
+        match self {
+            Terminator::Lf => "\n",
+            Terminator::CrLf => "\r\n",
+        }
+    }
+}
+
+pub struct Writer {
+    buf: RedString,
+    delimiter: char,
+    quote: char,
+    terminator: Terminator,
+    fields: usize,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::from_buf(RedString::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_buf(RedString::with_capacity(capacity))
+    }
+
+    pub fn with_delimiter(delimiter: char) -> Self {
+        let mut writer = Self::new();
+        writer.set_delimiter(delimiter);
+        writer
+    }
+
+    pub fn from_buf(buf: RedString) -> Self {
+        Self {
+            buf,
+            delimiter: ',',
+            quote: '"',
+            terminator: Terminator::Lf,
+            fields: 0,
+        }
+    }
+
+    pub fn set_delimiter(&mut self, delimiter: char) {
+        self.delimiter = delimiter;
+    }
+
+    pub fn set_quote(&mut self, quote: char) {
+        self.quote = quote;
+    }
+
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = terminator;
+    }
+
+    pub fn write_field(&mut self, field: &str) {
+        if self.fields > 0 {
+            self.buf.push(self.delimiter);
+        }
+        self.fields += 1;
+
+        if !self.needs_quotes(field) {
+            self.buf.push_str(field);
+            return;
+        }
+
+        self.buf.push(self.quote);
+        let mut rest = field;
+        while let Some(idx) = rest.find(self.quote) {
+            let end = idx + self.quote.len_utf8();
+            self.buf.push_str(&rest[..end]);
+            self.buf.push(self.quote);
+            rest = &rest[end..];
+        }
+        self.buf.push_str(rest);
+        self.buf.push(self.quote);
+    }
+
+    pub fn write_record<I, T>(&mut self, record: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        for field in record {
+            self.write_field(field.as_ref());
+        }
+        self.end_record();
+    }
+
+    pub fn end_record(&mut self) {
+        self.buf.push_str(self.terminator.as_str());
+        self.fields = 0;
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.buf.as_str()
+    }
+
+    pub fn into_inner(self) -> RedString {
+        self.buf
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        self.buf.into_rstring()
+    }
+
+    fn needs_quotes(&self, field: &str) -> bool {
+        field.is_empty()
+            || field
+                .chars()
+                .any(|c| c == self.delimiter || c == self.quote || c == '\r' || c == '\n')
+    }
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{Terminator, Writer};
+
+    #[ruby_test]
+    fn test_write_record() {
+        let mut w = Writer::new();
+        w.write_record(["a", "b", "c"]);
+        w.write_record(["1", "2", "3"]);
+        assert_eq!(w.as_str(), "a,b,c\n1,2,3\n");
+    }
+
+    #[ruby_test]
+    fn test_quoting() {
+        let mut w = Writer::new();
+        w.write_record(["a,b", "say \"hi\"", "line\nbreak", ""]);
+        assert_eq!(
+            w.as_str(),
+            "\"a,b\",\"say \"\"hi\"\"\",\"line\nbreak\",\"\"\n"
+        );
+    }
+
+    #[ruby_test]
+    fn test_empty_field() {
+        let mut w = Writer::new();
+        w.write_record([""]);
+        w.end_record();
+        assert_eq!(w.as_str(), "\"\"\n\n");
+    }
+
+    #[ruby_test]
+    fn test_delimiter() {
+        let mut w = Writer::with_delimiter(';');
+        w.set_terminator(Terminator::CrLf);
+        w.write_record(["a,b", "c;d"]);
+        assert_eq!(w.as_str(), "a,b;\"c;d\"\r\n");
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_into_rstring() {
+        let mut w = Writer::new();
+        w.write_record(["a", "b"]);
+        assert_eq!(w.into_rstring().to_string().unwrap(), "a,b\n");
+    }
+}
@@ -0,0 +1,421 @@
+use magnus::{
+    prelude::*,
+    value::{Qfalse, Qtrue},
+    Float, Integer, RArray, RHash, RString, Symbol, Value,
+};
+use serde::de::{self, DeserializeOwned, Visitor};
+
+use crate::{RedBytes, RedString};
+
+#[derive(Debug)]
+pub enum DeError {
+    Message(String),
+    Ruby(magnus::Error),
+}
+
+impl std::fmt::Display for DeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeError::Message(msg) => f.write_str(msg),
+            DeError::Ruby(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        DeError::Message(msg.to_string())
+    }
+}
+
+impl From<magnus::Error> for DeError {
+    fn from(err: magnus::Error) -> Self {
+        DeError::Ruby(err)
+    }
+}
+
+// A shape mismatch is what `TryConvert` would report as a `TypeError`.
+impl From<DeError> for magnus::Error {
+    fn from(err: DeError) -> Self {
+        match err {
+            DeError::Message(msg) => magnus::Error::new(magnus::exception::type_error(), msg),
+            DeError::Ruby(err) => err,
+        }
+    }
+}
+
+// Builds a `T` straight from Ruby objects, without going through an
+// intermediate format: hashes become maps and structs (keys may be strings
+// or symbols), arrays become sequences, and symbols read as strings.
+// Strings are copied out only when a field takes them, so `RedString` and
+// `RedVec` fields end up on Ruby's heap like everything else here.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, DeError> {
+    T::deserialize(Deserializer::new(value))
+}
+
+#[derive(Clone, Copy)]
+pub struct Deserializer {
+    value: Value,
+}
+
+impl Deserializer {
+    pub fn new(value: Value) -> Self {
+        Self { value }
+    }
+
+    fn unexpected(&self, expected: &str) -> DeError {
+        DeError::Message(format!("expected {}, got {}", expected, unsafe {
+            self.value.classname()
+        }))
+    }
+
+    fn with_str<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if let Some(sym) = Symbol::from_value(self.value) {
+            return visitor.visit_str(&sym.name()?);
+        }
+        match RString::from_value(self.value) {
+            // The visitor copies what it needs before it can call back into Ruby.
+            Some(rstring) => visitor.visit_str(unsafe { rstring.as_str()? }),
+            None => Err(self.unexpected("a String or Symbol")),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let value = self.value;
+        if value.is_nil() {
+            visitor.visit_unit()
+        } else if Qtrue::from_value(value).is_some() {
+            visitor.visit_bool(true)
+        } else if Qfalse::from_value(value).is_some() {
+            visitor.visit_bool(false)
+        } else if let Some(int) = Integer::from_value(value) {
+            match int.to_i64() {
+                Ok(n) => visitor.visit_i64(n),
+                Err(_) => visitor.visit_u64(int.to_u64()?),
+            }
+        } else if let Some(float) = Float::from_value(value) {
+            visitor.visit_f64(float.to_f64())
+        } else if RString::from_value(value).is_some() || Symbol::from_value(value).is_some() {
+            self.with_str(visitor)
+        } else if let Some(array) = RArray::from_value(value) {
+            visitor.visit_seq(SeqAccess { array, index: 0 })
+        } else if let Some(hash) = RHash::from_value(value) {
+            visitor.visit_map(MapAccess::new(hash)?)
+        } else {
+            Err(self.unexpected("nil, a boolean, number, String, Symbol, Array or Hash"))
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.with_str(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.with_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.with_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match RString::from_value(self.value) {
+            Some(rstring) => visitor.visit_bytes(unsafe { rstring.as_slice() }),
+            None => Err(self.unexpected("a String")),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.value.is_nil() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // `:variant`, `"variant"` or `{ variant: value }`.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        if let Some(hash) = RHash::from_value(self.value) {
+            let entries = MapAccess::new(hash)?;
+            if entries.len() != 1 {
+                return Err(self.unexpected("a Hash with a single key"));
+            }
+            visitor.visit_enum(EnumAccess {
+                variant: entries.pairs.entry(0)?,
+                value: Some(entries.pairs.entry(1)?),
+            })
+        } else {
+            visitor.visit_enum(EnumAccess {
+                variant: self.value,
+                value: None,
+            })
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char unit
+        unit_struct seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct SeqAccess {
+    array: RArray,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = DeError;
+
+    // Indexes on every step rather than borrowing the array's storage, since
+    // element visitors may call back into Ruby.
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        if self.index >= self.array.len() {
+            return Ok(None);
+        }
+        let value: Value = self.array.entry(self.index as isize)?;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(value)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.array.len().saturating_sub(self.index))
+    }
+}
+
+// Keys and values are copied into an array rather than Rust memory, so the
+// GC marks them and updates them if compaction moves them while a visitor
+// allocates. The array itself is only referenced from here, and `MapAccess`
+// only ever lives on the stack.
+struct MapAccess {
+    pairs: RArray,
+    index: usize,
+}
+
+impl MapAccess {
+    fn new(hash: RHash) -> Result<Self, DeError> {
+        let pairs = RArray::with_capacity(2 * hash.len());
+        hash.foreach(|key: Value, value: Value| {
+            pairs.push(key)?;
+            pairs.push(value)?;
+            Ok(magnus::r_hash::ForEach::Continue)
+        })?;
+        Ok(Self { pairs, index: 0 })
+    }
+
+    fn len(&self) -> usize {
+        (self.pairs.len() - self.index) / 2
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = DeError;
+
+    // Leaves `index` on the key until its value has been read.
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        if self.index >= self.pairs.len() {
+            return Ok(None);
+        }
+        let key: Value = self.pairs.entry(self.index as isize)?;
+        seed.deserialize(Deserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DeError> {
+        assert!(
+            self.index < self.pairs.len(),
+            "next_value called before next_key"
+        );
+        let value: Value = self.pairs.entry(self.index as isize + 1)?;
+        self.index += 2;
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+struct EnumAccess {
+    variant: Value,
+    value: Option<Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = DeError;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess), DeError> {
+        let variant = seed.deserialize(Deserializer::new(self.variant))?;
+        Ok((variant, VariantAccess { value: self.value }))
+    }
+}
+
+struct VariantAccess {
+    value: Option<Value>,
+}
+
+impl VariantAccess {
+    fn value(self) -> Result<Deserializer, DeError> {
+        self.value
+            .map(Deserializer::new)
+            .ok_or_else(|| DeError::Message("expected a Hash for a non-unit variant".to_owned()))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        match self.value {
+            Some(value) => de::Deserialize::deserialize(Deserializer::new(value)),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DeError> {
+        seed.deserialize(self.value()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_seq(self.value()?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_map(self.value()?, visitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RedString {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RedStringVisitor;
+
+        impl Visitor<'_> for RedStringVisitor {
+            type Value = RedString;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<RedString, E> {
+                Ok(RedString::from_str(s))
+            }
+        }
+
+        deserializer.deserialize_str(RedStringVisitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RedBytes {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RedBytesVisitor;
+
+        impl Visitor<'_> for RedBytesVisitor {
+            type Value = RedBytes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a byte string")
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<RedBytes, E> {
+                Ok(RedBytes::from_slice(bytes))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<RedBytes, E> {
+                Ok(RedBytes::from_slice(s.as_bytes()))
+            }
+        }
+
+        deserializer.deserialize_bytes(RedBytesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::{exception, prelude::*};
+    use rb_sys_test_helpers::ruby_test;
+    use serde::Deserialize;
+
+    use super::from_value;
+    use crate::{RedString, RedVec};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Fast,
+        Limit(u32),
+    }
+
+    #[derive(Deserialize)]
+    struct Options {
+        name: RedString,
+        tags: RedVec<RedString>,
+        retries: Option<u8>,
+        ratio: f64,
+        modes: Vec<Mode>,
+    }
+
+    #[ruby_test]
+    fn test_from_value() {
+        let value = magnus::eval(
+            r#"{ name: "red", "tags" => ["a", :b], ratio: 0.5, modes: [:Fast, { "Limit" => 3 }] }"#,
+        )
+        .unwrap();
+        let options: Options = from_value(value).unwrap();
+        assert_eq!(options.name.as_str(), "red");
+        let tags: Vec<_> = options.tags.iter().map(|t| t.as_str()).collect();
+        assert_eq!(tags, ["a", "b"]);
+        assert_eq!(options.retries, None);
+        assert_eq!(options.ratio, 0.5);
+        assert_eq!(options.modes, [Mode::Fast, Mode::Limit(3)]);
+    }
+
+    #[ruby_test]
+    fn test_from_value_errors() {
+        let value = magnus::eval(r#"{ name: 1, tags: [], ratio: 1.0, modes: [] }"#).unwrap();
+        let err: magnus::Error = from_value::<Options>(value).err().unwrap().into();
+        assert!(err.is_kind_of(exception::type_error()));
+
+        let value = magnus::eval("[300]").unwrap();
+        assert!(from_value::<Vec<u8>>(value).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use encoding_rs::{CoderResult, Decoder, DecoderResult, Encoding};
+
+use crate::{DecodeError, RedString};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Malformed {
+    // Each malformed sequence becomes U+FFFD, so decoding never fails.
+    #[default]
+    Replace,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncodingError {
+    UnknownEncoding(String),
+    Decode(DecodeError),
+}
+
+impl std::fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodingError::UnknownEncoding(label) => write!(f, "unknown encoding name - {}", label),
+            EncodingError::Decode(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+impl From<DecodeError> for EncodingError {
+    fn from(err: DecodeError) -> Self {
+        EncodingError::Decode(err)
+    }
+}
+
+impl RedString {
+    // `label` is anything the WHATWG Encoding Standard accepts, e.g.
+    // "Windows-1252", "latin1" or "Shift_JIS".
+    pub fn from_encoded_bytes(
+        bytes: &[u8],
+        label: &str,
+        malformed: Malformed,
+    ) -> Result<Self, EncodingError> {
+        let mut decoder = ForeignDecoder::new(label, malformed)?;
+        decoder.push_bytes(bytes)?;
+        Ok(decoder.finish()?)
+    }
+
+    pub fn from_utf16le(bytes: &[u8]) -> Self {
+        decode_replacing(bytes, encoding_rs::UTF_16LE)
+    }
+
+    pub fn from_utf16be(bytes: &[u8]) -> Self {
+        decode_replacing(bytes, encoding_rs::UTF_16BE)
+    }
+}
+
+fn decode_replacing(bytes: &[u8], encoding: &'static Encoding) -> RedString {
+    let mut decoder = ForeignDecoder::for_encoding(encoding, Malformed::Replace);
+    decoder.push_bytes(bytes).unwrap();
+    decoder.finish().unwrap()
+}
+
+// Like `StreamingDecoder`, but for input in any encoding `encoding_rs`
+// knows, decoded straight into the UTF-8 buffer. A leading BOM for the
+// decoder's own encoding is dropped; any other BOM is kept as text.
+pub struct ForeignDecoder {
+    string: RedString,
+    decoder: Decoder,
+    malformed: Malformed,
+    consumed: usize,
+}
+
+impl ForeignDecoder {
+    pub fn new(label: &str, malformed: Malformed) -> Result<Self, EncodingError> {
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| EncodingError::UnknownEncoding(label.to_owned()))?;
+        Ok(Self::for_encoding(encoding, malformed))
+    }
+
+    pub fn for_encoding(encoding: &'static Encoding, malformed: Malformed) -> Self {
+        Self {
+            string: RedString::new(),
+            decoder: encoding.new_decoder_with_bom_removal(),
+            malformed,
+            consumed: 0,
+        }
+    }
+
+    pub fn encoding(&self) -> &'static Encoding {
+        self.decoder.encoding()
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.string.as_str()
+    }
+
+    // On error everything before the malformed sequence has been appended and
+    // the rest of `bytes` is dropped.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), DecodeError> {
+        self.decode(bytes, false)
+            .map_err(|offset| DecodeError::InvalidSequence { offset })
+    }
+
+    // Anything still held back at this point is a sequence cut off by the end
+    // of the input.
+    pub fn finish(mut self) -> Result<RedString, DecodeError> {
+        self.decode(&[], true)
+            .map_err(|offset| DecodeError::Incomplete { offset })?;
+        Ok(self.string)
+    }
+
+    // Returns the stream offset of the malformed sequence on error.
+    fn decode(&mut self, bytes: &[u8], last: bool) -> Result<(), usize> {
+        let max = match self.malformed {
+            Malformed::Replace => self.decoder.max_utf8_buffer_length(bytes.len()),
+            Malformed::Error => self
+                .decoder
+                .max_utf8_buffer_length_without_replacement(bytes.len()),
+        }
+        .expect("decoded length overflows usize");
+
+        // Zero bytes are valid UTF-8, so the string stays valid while the
+        // decoder writes over them.
+        let len = self.string.len();
+        crate::growth::reserve(&mut self.string.buf, max);
+        self.string.buf.resize(len + max, 0);
+        let dst = &mut self.string.buf[len..];
+
+        let (read, written, result) = match self.malformed {
+            Malformed::Replace => {
+                let (result, read, written, _) = self.decoder.decode_to_utf8(bytes, dst, last);
+                debug_assert_eq!(result, CoderResult::InputEmpty);
+                (read, written, Ok(()))
+            }
+            Malformed::Error => {
+                let (result, read, written) = self
+                    .decoder
+                    .decode_to_utf8_without_replacement(bytes, dst, last);
+                let result = match result {
+                    DecoderResult::InputEmpty => Ok(()),
+                    DecoderResult::OutputFull => unreachable!("buffer sized by the decoder"),
+                    DecoderResult::Malformed(bad, after) => {
+                        Err(self.consumed + read - after as usize - bad as usize)
+                    }
+                };
+                (read, written, result)
+            }
+        };
+        self.string.buf.truncate(len + written);
+        self.consumed += read;
+        result
+    }
+}
+
+impl std::io::Write for ForeignDecoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.push_bytes(buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{EncodingError, ForeignDecoder, Malformed};
+    use crate::{DecodeError, RedString};
+
+    #[ruby_test]
+    fn test_from_encoded_bytes() {
+        let s = RedString::from_encoded_bytes(b"caf\xE9 \x80", "Windows-1252", Malformed::Error)
+            .unwrap();
+        assert_eq!(s.as_str(), "café €");
+
+        let err =
+            RedString::from_encoded_bytes(b"abc", "NOT-AN-ENCODING", Malformed::Replace).err();
+        assert_eq!(
+            err,
+            Some(EncodingError::UnknownEncoding("NOT-AN-ENCODING".to_owned()))
+        );
+    }
+
+    #[ruby_test]
+    fn test_from_utf16() {
+        assert_eq!(
+            RedString::from_utf16le(b"\xFF\xFEh\x00\xE9\x00").as_str(),
+            "hé"
+        );
+        assert_eq!(
+            RedString::from_utf16be(b"\x00h\xD8\x00").as_str(),
+            "h\u{FFFD}"
+        );
+    }
+
+    #[ruby_test]
+    fn test_streaming() {
+        let input = b"\x82\xA0\x82\xA2x";
+        for chunk in 1..input.len() {
+            let mut decoder = ForeignDecoder::new("Shift_JIS", Malformed::Error).unwrap();
+            for piece in input.chunks(chunk) {
+                decoder.push_bytes(piece).unwrap();
+            }
+            assert_eq!(decoder.finish().unwrap().as_str(), "あいx");
+        }
+    }
+
+    #[ruby_test]
+    fn test_malformed() {
+        let mut decoder = ForeignDecoder::new("Shift_JIS", Malformed::Error).unwrap();
+        decoder.push_bytes(b"ok").unwrap();
+        assert_eq!(
+            decoder.push_bytes(b"\x82\xA0\xFFno"),
+            Err(DecodeError::InvalidSequence { offset: 4 })
+        );
+        assert_eq!(decoder.as_str(), "okあ");
+
+        let mut decoder = ForeignDecoder::new("Shift_JIS", Malformed::Error).unwrap();
+        decoder.push_bytes(b"ab\x82").unwrap();
+        assert_eq!(
+            decoder.finish().err(),
+            Some(DecodeError::Incomplete { offset: 2 })
+        );
+    }
+}
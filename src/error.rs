@@ -0,0 +1,149 @@
+use crate::DecodeError;
+
+// One error type for the fallible (`try_`) API, so callers can `?` through a
+// mix of index, UTF-8, allocation and Ruby failures. With `magnus` it
+// converts into the matching Ruby exception.
+#[derive(Debug)]
+pub enum RedError {
+    Utf8(std::str::Utf8Error),
+    // `index` is past the end of the string, or not on a char boundary.
+    Bounds {
+        index: usize,
+        len: usize,
+    },
+    Alloc,
+    Encoding(String),
+    #[cfg(feature = "magnus")]
+    RubyException(magnus::Error),
+}
+
+impl std::fmt::Display for RedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedError::Utf8(err) => err.fmt(f),
+            RedError::Bounds { index, len } => {
+                write!(
+                    f,
+                    "index {} out of bounds or not a char boundary (len {})",
+                    index, len
+                )
+            }
+            RedError::Alloc => write!(f, "failed to allocate memory"),
+            RedError::Encoding(msg) => f.write_str(msg),
+            #[cfg(feature = "magnus")]
+            RedError::RubyException(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RedError::Utf8(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for RedError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        RedError::Utf8(err)
+    }
+}
+
+impl From<allocator_api2::alloc::AllocError> for RedError {
+    fn from(_: allocator_api2::alloc::AllocError) -> Self {
+        RedError::Alloc
+    }
+}
+
+impl From<DecodeError> for RedError {
+    fn from(err: DecodeError) -> Self {
+        RedError::Encoding(err.to_string())
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+impl From<crate::EncodingError> for RedError {
+    fn from(err: crate::EncodingError) -> Self {
+        RedError::Encoding(err.to_string())
+    }
+}
+
+#[cfg(feature = "magnus")]
+impl From<crate::transcode::TranscodeError> for RedError {
+    fn from(err: crate::transcode::TranscodeError) -> Self {
+        RedError::Encoding(err.to_string())
+    }
+}
+
+#[cfg(feature = "magnus")]
+impl From<magnus::Error> for RedError {
+    fn from(err: magnus::Error) -> Self {
+        RedError::RubyException(err)
+    }
+}
+
+// Same classes Ruby raises for the equivalent `String` failures.
+#[cfg(feature = "magnus")]
+impl From<RedError> for magnus::Error {
+    fn from(err: RedError) -> Self {
+        use magnus::exception;
+
+        let class = match err {
+            RedError::RubyException(err) => return err,
+            RedError::Utf8(_) | RedError::Encoding(_) => exception::encoding_error(),
+            RedError::Bounds { .. } => exception::index_error(),
+            RedError::Alloc => exception::no_mem_error(),
+        };
+        magnus::Error::new(class, err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RedError;
+    use crate::{RedBytes, RedString};
+
+    #[ruby_test]
+    fn test_try_insert_remove() {
+        let mut s = RedString::from_str("héllo");
+        assert!(matches!(
+            s.try_insert(2, 'x'),
+            Err(RedError::Bounds { index: 2, len: 6 })
+        ));
+        assert!(matches!(
+            s.try_insert_str(7, "x"),
+            Err(RedError::Bounds { .. })
+        ));
+        s.try_insert_str(6, "!").unwrap();
+        assert!(matches!(s.try_remove(7), Err(RedError::Bounds { .. })));
+        assert_eq!(s.try_remove(1).unwrap(), 'é');
+        assert_eq!(s.as_str(), "hllo!");
+    }
+
+    #[ruby_test]
+    fn test_try_from_bytes() {
+        let s = RedString::try_from(RedBytes::from_slice(b"ok")).unwrap();
+        assert_eq!(s.as_str(), "ok");
+        assert!(matches!(
+            RedString::try_from(RedBytes::from_slice(b"o\xFFk")),
+            Err(RedError::Utf8(e)) if e.valid_up_to() == 1
+        ));
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_into_ruby_exception() {
+        use magnus::{exception, prelude::*};
+
+        let err: magnus::Error = RedError::Bounds { index: 3, len: 1 }.into();
+        assert!(err.is_kind_of(exception::index_error()));
+
+        let raised = magnus::eval::<magnus::Value>("raise ArgumentError, 'nope'").unwrap_err();
+        let err: magnus::Error = RedError::from(raised).into();
+        assert!(err.is_kind_of(exception::arg_error()));
+    }
+}
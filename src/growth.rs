@@ -0,0 +1,195 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "protected")]
+use crate::protect::fallible;
+use crate::RedVec;
+
+// How `RedString` and `RedBytes` pick a new capacity when a push doesn't
+// fit. Doubling, like `Vec`, keeps appends amortized O(1), but a builder
+// that just crossed 1 GiB holds 2 GiB right before handing off, and all of
+// it counts towards Ruby's malloc limit. The others trade more reallocations
+// for less overshoot; `Exact` never allocates more than asked for, so
+// callers should `reserve` up front when they know the final size.
+//
+// Only growth done on a push or `reserve` follows the policy; `with_capacity`
+// is always exact.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Growth {
+    #[default]
+    Double,
+    OneAndHalf,
+    // Grows by whole multiples of the step, which must not be zero.
+    Step(usize),
+    Exact,
+}
+
+impl Growth {
+    fn capacity_for(self, capacity: usize, required: usize) -> usize {
+        match self {
+            Growth::Double => (capacity.saturating_mul(2)).max(required).max(8),
+            Growth::OneAndHalf => capacity.saturating_add(capacity / 2).max(required).max(8),
+            Growth::Step(step) => {
+                let steps = (required - capacity).div_ceil(step);
+                capacity
+                    .checked_add(steps.saturating_mul(step))
+                    .unwrap_or(required)
+            }
+            Growth::Exact => required,
+        }
+    }
+
+    fn encode(self) -> usize {
+        match self {
+            Growth::Double => 0,
+            Growth::OneAndHalf => 1,
+            Growth::Exact => 2,
+            Growth::Step(step) => {
+                assert!(
+                    step > 0 && step <= usize::MAX >> 2,
+                    "growth step out of range"
+                );
+                step << 2 | 3
+            }
+        }
+    }
+
+    fn decode(bits: usize) -> Self {
+        match bits & 3 {
+            0 => Growth::Double,
+            1 => Growth::OneAndHalf,
+            2 => Growth::Exact,
+            _ => Growth::Step(bits >> 2),
+        }
+    }
+}
+
+// Process-wide, like the allocator itself.
+static GROWTH: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static OVERRIDE: Cell<Option<Growth>> = const { Cell::new(None) };
+}
+
+pub fn set_growth(growth: Growth) {
+    GROWTH.store(growth.encode(), Ordering::Relaxed);
+}
+
+// The policy in effect on the current thread: the innermost `with_growth`,
+// if any, otherwise the one set with `set_growth`.
+pub fn growth() -> Growth {
+    OVERRIDE
+        .with(Cell::get)
+        .unwrap_or_else(|| Growth::decode(GROWTH.load(Ordering::Relaxed)))
+}
+
+// Uses `growth` for everything built on this thread while `f` runs, e.g. to
+// build one large response exactly without changing the default for the
+// rest of the process.
+pub fn with_growth<R>(growth: Growth, f: impl FnOnce() -> R) -> R {
+    assert!(growth != Growth::Step(0), "growth step out of range");
+
+    struct Restore(Option<Growth>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            OVERRIDE.with(|o| o.set(self.0));
+        }
+    }
+
+    let _restore = Restore(OVERRIDE.with(|o| o.replace(Some(growth))));
+    f()
+}
+
+pub(crate) fn reserve<T>(buf: &mut RedVec<T>, additional: usize) {
+    if buf.capacity() - buf.len() >= additional {
+        return;
+    }
+    match target(buf, additional) {
+        Some(capacity) => buf.reserve_exact(capacity - buf.len()),
+        None => buf.reserve(additional),
+    }
+}
+
+pub(crate) fn try_reserve<T>(
+    buf: &mut RedVec<T>,
+    additional: usize,
+) -> Result<(), allocator_api2::alloc::AllocError> {
+    if buf.capacity() - buf.len() >= additional {
+        return Ok(());
+    }
+    let target = target(buf, additional);
+    fallible(|| match target {
+        Some(capacity) => buf.try_reserve_exact(capacity - buf.len()),
+        None => buf.try_reserve(additional),
+    })
+    .map_err(|_| allocator_api2::alloc::AllocError)
+}
+
+// `None` for doubling, which is what `Vec` does already, and on overflow,
+// which `Vec` reports.
+fn target<T>(buf: &RedVec<T>, additional: usize) -> Option<usize> {
+    let required = buf.len().checked_add(additional)?;
+    match growth() {
+        Growth::Double => None,
+        growth => Some(growth.capacity_for(buf.capacity(), required)),
+    }
+}
+
+// Without the `protected` feature a failed Ruby allocation always raises.
+#[cfg(not(feature = "protected"))]
+fn fallible<T>(f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{growth, with_growth, Growth};
+    use crate::{RedBytes, RedString};
+
+    #[test]
+    fn test_capacity_for() {
+        assert_eq!(Growth::Double.capacity_for(100, 101), 200);
+        assert_eq!(Growth::OneAndHalf.capacity_for(100, 101), 150);
+        assert_eq!(Growth::OneAndHalf.capacity_for(100, 400), 400);
+        assert_eq!(Growth::Step(64).capacity_for(100, 101), 164);
+        assert_eq!(Growth::Step(64).capacity_for(100, 300), 356);
+        assert_eq!(Growth::Exact.capacity_for(100, 101), 101);
+
+        for growth in [
+            Growth::Double,
+            Growth::OneAndHalf,
+            Growth::Step(4096),
+            Growth::Exact,
+        ] {
+            assert_eq!(Growth::decode(growth.encode()), growth);
+        }
+    }
+
+    #[ruby_test]
+    fn test_with_growth() {
+        let outer = growth();
+        with_growth(Growth::Exact, || {
+            assert_eq!(growth(), Growth::Exact);
+
+            let mut s = RedString::with_capacity(10);
+            s.push_str("0123456789");
+            s.push('!');
+            assert_eq!(s.capacity(), 11);
+            s.reserve(5);
+            assert_eq!(s.capacity(), 16);
+
+            with_growth(Growth::Step(100), || {
+                let mut b = RedBytes::with_capacity(10);
+                b.extend_from_slice(&[0; 11]);
+                assert_eq!(b.capacity(), 110);
+            });
+            assert_eq!(growth(), Growth::Exact);
+        });
+        assert_eq!(growth(), outer);
+    }
+}
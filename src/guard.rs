@@ -0,0 +1,116 @@
+use magnus::rb_sys::{protect, AsRawValue};
+
+// Gives mutable access to the bytes of an existing Ruby string. The string is
+// made independent of any shared buffer and locked for the lifetime of the
+// guard, so Ruby code can't resize or free the buffer underneath us; any
+// attempt to modify it from Ruby raises instead.
+pub struct RStringMutGuard<'a> {
+    rstring: &'a magnus::RString,
+}
+
+impl<'a> RStringMutGuard<'a> {
+    pub fn new(rstring: &'a magnus::RString) -> Result<Self, magnus::Error> {
+        let raw = rstring.as_raw();
+        protect(|| unsafe {
+            rb_sys::rb_str_modify(raw);
+            rb_sys::rb_str_locktmp(raw)
+        })?;
+        Ok(Self { rstring })
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { rb_sys::RSTRING_LEN(self.rstring.as_raw()) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        let raw = self.rstring.as_raw();
+        unsafe { std::slice::from_raw_parts(rb_sys::RSTRING_PTR(raw) as *const u8, self.len()) }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let raw = self.rstring.as_raw();
+        unsafe { std::slice::from_raw_parts_mut(rb_sys::RSTRING_PTR(raw) as *mut u8, self.len()) }
+    }
+
+    // `None` unless the string is UTF-8 (or US-ASCII) and its contents are
+    // valid, so edits through the `&mut str` keep the Ruby string valid too.
+    pub fn as_mut_str(&mut self) -> Option<&mut str> {
+        if !self.rstring.is_utf8_compatible_encoding() {
+            return None;
+        }
+        std::str::from_utf8_mut(self.as_bytes_mut()).ok()
+    }
+}
+
+// Ruby code run while the guard was alive may have cached a coderange that
+// our writes made stale, so it is cleared again for Ruby to rescan.
+impl Drop for RStringMutGuard<'_> {
+    fn drop(&mut self) {
+        let raw = self.rstring.as_raw();
+        unsafe {
+            crate::clear_coderange(raw);
+            rb_sys::rb_str_unlocktmp(raw);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::prelude::*;
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RStringMutGuard;
+
+    #[ruby_test]
+    fn test_as_bytes_mut() {
+        let s = magnus::RString::new("hello");
+        {
+            let mut guard = RStringMutGuard::new(&s).unwrap();
+            guard.as_bytes_mut()[0] = b'j';
+        }
+        assert_eq!(s.to_string().unwrap(), "jello");
+    }
+
+    #[ruby_test]
+    fn test_as_mut_str() {
+        let s = magnus::RString::new("héllo");
+        {
+            let mut guard = RStringMutGuard::new(&s).unwrap();
+            guard.as_mut_str().unwrap().make_ascii_uppercase();
+        }
+        assert_eq!(s.to_string().unwrap(), "HéLLO");
+    }
+
+    #[ruby_test]
+    fn test_coderange_rescanned() {
+        let s = magnus::RString::new("hello");
+        {
+            let mut guard = RStringMutGuard::new(&s).unwrap();
+            let valid: bool = s.funcall("valid_encoding?", ()).unwrap();
+            assert!(valid);
+            guard.as_bytes_mut()[0] = 0xff;
+        }
+        let valid: bool = s.funcall("valid_encoding?", ()).unwrap();
+        assert!(!valid);
+    }
+
+    #[ruby_test]
+    fn test_locked() {
+        let s = magnus::RString::new("hello");
+        let guard = RStringMutGuard::new(&s).unwrap();
+        assert!(RStringMutGuard::new(&s).is_err());
+        drop(guard);
+        assert!(RStringMutGuard::new(&s).is_ok());
+    }
+
+    #[ruby_test]
+    fn test_frozen() {
+        let s = magnus::RString::new("hello");
+        s.freeze();
+        assert!(RStringMutGuard::new(&s).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{mem::ManuallyDrop, os::raw::c_int};
+
+use crate::{RedBytes, RedString, RedVec};
+
+// Ruby does not expose this publicly, but magnus and fiddle rely on it too.
+extern "C" {
+    fn ruby_thread_has_gvl_p() -> c_int;
+}
+
+pub(crate) fn has_gvl() -> bool {
+    unsafe { ruby_thread_has_gvl_p() != 0 }
+}
+
+// Runs `f` with the GVL held, reacquiring it if this thread released it.
+// Threads Ruby doesn't know about can't take the GVL at all, so using a
+// Ruby-allocated buffer there is a bug we'd rather hear about loudly.
+pub(crate) fn with_gvl<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    try_with_gvl(f).expect("Ruby-allocated buffer used on a thread not created by Ruby")
+}
+
+// Like `with_gvl`, but `None` instead of a panic on threads Ruby doesn't
+// know about, for callers that can report the failure some other way.
+pub(crate) fn try_with_gvl<F, T>(f: F) -> Option<T>
+where
+    F: FnOnce() -> T,
+{
+    struct Call<F, T> {
+        f: Option<F>,
+        result: Option<T>,
+    }
+
+    unsafe extern "C" fn call<F, T>(arg: *mut libc::c_void) -> *mut libc::c_void
+    where
+        F: FnOnce() -> T,
+    {
+        let call = &mut *(arg as *mut Call<F, T>);
+        call.result = Some((call.f.take().unwrap())());
+        std::ptr::null_mut()
+    }
+
+    if has_gvl() {
+        return Some(f());
+    }
+    if unsafe { rb_sys::ruby_native_thread_p() } == 0 {
+        return None;
+    }
+
+    let mut args = Call {
+        f: Some(f),
+        result: None,
+    };
+    unsafe {
+        rb_sys::rb_thread_call_with_gvl(
+            Some(call::<F, T>),
+            &mut args as *mut Call<F, T> as *mut libc::c_void,
+        );
+    }
+    args.result
+}
+
+/// Types that may move to another Ruby thread as long as they are only used
+/// and dropped while holding the GVL.
+///
+/// # Safety
+///
+/// Implementors must not share state with values left on the original thread
+/// (no `Rc`, thread locals, or borrowed data), and must have no interior
+/// mutability, since [`GvlBound`] hands out `&T` on several threads.
+pub unsafe trait GvlSend {}
+
+unsafe impl GvlSend for RedString {}
+unsafe impl GvlSend for RedBytes {}
+unsafe impl<T: GvlSend> GvlSend for RedVec<T> {}
+
+macro_rules! gvl_send_primitives {
+    ($($ty:ty),*) => {
+        $(unsafe impl GvlSend for $ty {})*
+    };
+}
+
+gvl_send_primitives!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+// Containers using `RubyAllocator` are neither `Send` nor `Sync`, because
+// growing or dropping them calls into Ruby, which needs the GVL. This wrapper
+// moves one between threads anyway and checks for the GVL on the other side:
+// access panics without it, and dropping reacquires it.
+pub struct GvlBound<T: GvlSend> {
+    value: ManuallyDrop<T>,
+}
+
+unsafe impl<T: GvlSend> Send for GvlBound<T> {}
+unsafe impl<T: GvlSend> Sync for GvlBound<T> {}
+
+impl<T: GvlSend> GvlBound<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+        }
+    }
+
+    pub fn try_get(&self) -> Option<&T> {
+        has_gvl().then_some(&*self.value)
+    }
+
+    pub fn get(&self) -> &T {
+        self.try_get().expect("GvlBound accessed without the GVL")
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        assert!(has_gvl(), "GvlBound accessed without the GVL");
+        &mut self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        assert!(has_gvl(), "GvlBound accessed without the GVL");
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::take(&mut this.value) }
+    }
+}
+
+impl<T: GvlSend> Drop for GvlBound<T> {
+    fn drop(&mut self) {
+        let value = &mut self.value;
+        with_gvl(|| unsafe { ManuallyDrop::drop(value) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::GvlBound;
+    use crate::RedString;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[ruby_test]
+    fn test_gvl_bound() {
+        let mut bound = GvlBound::new(RedString::from_str("hello"));
+        assert_send_sync(&bound);
+        bound.get_mut().push_str(" world");
+        assert_eq!(bound.get().as_str(), "hello world");
+        assert_eq!(bound.into_inner().as_str(), "hello world");
+    }
+
+    #[ruby_test]
+    fn test_drop_without_gvl() {
+        unsafe extern "C" fn call(arg: *mut libc::c_void) -> *mut libc::c_void {
+            let bound = Box::from_raw(arg as *mut GvlBound<RedString>);
+            assert!(bound.try_get().is_none());
+            drop(bound);
+            std::ptr::null_mut()
+        }
+
+        let bound = Box::new(GvlBound::new(RedString::from_str("dropped")));
+        unsafe {
+            rb_sys::rb_thread_call_without_gvl(
+                Some(call),
+                Box::into_raw(bound) as *mut libc::c_void,
+                None,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::RedString;
+
+impl RedString {
+    // Same value as `rb_str_hash` on an equal UTF-8 Ruby string, which is
+    // what `Hash` uses for string keys.
+    pub fn hash_as_ruby(&self) -> u64 {
+        hash_bytes(self.as_bytes())
+    }
+}
+
+// Hashes string keys like Ruby does, so a map keyed by `RedString` or `&str`
+// agrees with a Ruby `Hash` on the hash of every key. `str`'s `Hash` impl
+// writes the bytes followed by a 0xff terminator; the terminator is skipped
+// so a single string hashes to exactly `hash_as_ruby`. Anything else written
+// is mixed in, so other key types still work, just not Ruby-compatibly.
+#[derive(Clone, Debug, Default)]
+pub struct RubyHasher {
+    hash: Option<u64>,
+    after_write: bool,
+}
+
+pub type RubyBuildHasher = BuildHasherDefault<RubyHasher>;
+
+impl Hasher for RubyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let hash = hash_bytes(bytes);
+        self.hash = Some(match self.hash {
+            Some(prev) => (prev.rotate_left(5) ^ hash).wrapping_mul(0x517c_c1b7_2722_0a95),
+            None => hash,
+        });
+        self.after_write = true;
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        if self.after_write && i == 0xff {
+            self.after_write = false;
+            return;
+        }
+        self.write(&[i]);
+        self.after_write = false;
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash.unwrap_or_else(|| hash_bytes(&[]))
+    }
+}
+
+// `rb_str_hash` also mixes in the encoding index, but only for strings that
+// aren't ASCII-only.
+pub(crate) fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash = unsafe {
+        rb_sys::rb_memhash(
+            bytes.as_ptr() as *const libc::c_void,
+            bytes.len().try_into().unwrap(),
+        )
+    } as u64;
+    if !bytes.is_ascii() {
+        hash ^= unsafe { rb_sys::rb_utf8_encindex() } as u64;
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        hash::{BuildHasher, Hash, Hasher},
+    };
+
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{RubyBuildHasher, RubyHasher};
+    use crate::RedString;
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_hash_as_ruby() {
+        use magnus::rb_sys::AsRawValue;
+
+        for s in ["", "plain", "héllo"] {
+            let rstring = magnus::RString::new(s);
+            let expected = unsafe { rb_sys::rb_str_hash(rstring.as_raw()) } as u64;
+            assert_eq!(RedString::from_str(s).hash_as_ruby(), expected);
+        }
+    }
+
+    #[ruby_test]
+    fn test_ruby_hasher() {
+        let s = RedString::from_str("key");
+        assert_eq!(RubyBuildHasher::default().hash_one(&s), s.hash_as_ruby());
+        assert_eq!(RubyBuildHasher::default().hash_one("key"), s.hash_as_ruby());
+
+        let mut pair = RubyHasher::default();
+        ("a", "b").hash(&mut pair);
+        let mut swapped = RubyHasher::default();
+        ("b", "a").hash(&mut swapped);
+        assert_ne!(pair.finish(), swapped.finish());
+
+        let mut map = HashMap::with_hasher(RubyBuildHasher::default());
+        map.insert(s, 1);
+        assert_eq!(map.get("key"), Some(&1));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::RedString;
+
+impl RedString {
+    // Escapes the same five characters as `ERB::Util.html_escape` and
+    // `CGI.escapeHTML`, with the same entities, so output matches what Ruby
+    // templates would produce.
+    pub fn push_html_escaped(&mut self, s: &str) {
+        self.reserve(s.len());
+        let mut start = 0;
+        for (idx, byte) in s.bytes().enumerate() {
+            let entity = match byte {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                b'\'' => "&#39;",
+                _ => continue,
+            };
+            self.push_str(&s[start..idx]);
+            self.push_str(entity);
+            start = idx + 1;
+        }
+        self.push_str(&s[start..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_push_html_escaped() {
+        let mut s = RedString::from_str("<p>");
+        s.push_html_escaped(r#"Tom & "Jerry's" <b>"#);
+        s.push_html_escaped("café");
+        assert_eq!(
+            s.as_str(),
+            "<p>Tom &amp; &quot;Jerry&#39;s&quot; &lt;b&gt;café"
+        );
+    }
+}
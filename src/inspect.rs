@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+use crate::RedString;
+
+impl RedString {
+    // Same output as `String#inspect` for a UTF-8 string with a UTF-8
+    // default external encoding. Unassigned code points are kept as-is,
+    // since telling them apart would need the full Unicode tables.
+    pub fn push_inspect(&mut self, s: &str) {
+        self.push_inspect_bytes(s.as_bytes(), true);
+    }
+
+    // `unicode` selects between UTF-8 (`\u` escapes, invalid bytes as `\x`)
+    // and binary strings, where every non-ASCII byte is a `\x` escape.
+    pub(crate) fn push_inspect_bytes(&mut self, bytes: &[u8], unicode: bool) {
+        self.reserve(bytes.len() + 2);
+        self.push('"');
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (valid, invalid) = if unicode {
+                match std::str::from_utf8(rest) {
+                    Ok(s) => (s, 0),
+                    Err(e) => (
+                        unsafe { std::str::from_utf8_unchecked(&rest[..e.valid_up_to()]) },
+                        e.error_len().unwrap_or(rest.len() - e.valid_up_to()),
+                    ),
+                }
+            } else {
+                let ascii = rest
+                    .iter()
+                    .position(|b| !b.is_ascii())
+                    .unwrap_or(rest.len());
+                (
+                    unsafe { std::str::from_utf8_unchecked(&rest[..ascii]) },
+                    (ascii < rest.len()) as usize,
+                )
+            };
+
+            let mut chars = valid.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => self.push_str("\\\""),
+                    '\\' => self.push_str("\\\\"),
+                    '#' if matches!(chars.peek(), Some('{' | '$' | '@')) => self.push_str("\\#"),
+                    '\n' => self.push_str("\\n"),
+                    '\r' => self.push_str("\\r"),
+                    '\t' => self.push_str("\\t"),
+                    '\x0c' => self.push_str("\\f"),
+                    '\x0b' => self.push_str("\\v"),
+                    '\x08' => self.push_str("\\b"),
+                    '\x07' => self.push_str("\\a"),
+                    '\x1b' => self.push_str("\\e"),
+                    c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                        if !unicode {
+                            write!(self, "\\x{:02X}", c as u32).unwrap();
+                        } else if (c as u32) < 0x10000 {
+                            write!(self, "\\u{:04X}", c as u32).unwrap();
+                        } else {
+                            write!(self, "\\u{{{:X}}}", c as u32).unwrap();
+                        }
+                    }
+                    c => self.push(c),
+                }
+            }
+
+            let start = valid.len();
+            for byte in &rest[start..start + invalid] {
+                write!(self, "\\x{:02X}", byte).unwrap();
+            }
+            rest = &rest[start + invalid..];
+        }
+        self.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    fn inspect(s: &str) -> RedString {
+        let mut result = RedString::new();
+        result.push_inspect(s);
+        result
+    }
+
+    #[ruby_test]
+    fn test_push_inspect() {
+        assert_eq!(inspect("plain").as_str(), r#""plain""#);
+        assert_eq!(inspect("say \"hi\"\\").as_str(), r#""say \"hi\"\\""#);
+        assert_eq!(inspect("a\n\t\x1b\x07").as_str(), r#""a\n\t\e\a""#);
+        assert_eq!(
+            inspect("#{x} #$y #@z #x").as_str(),
+            r#""\#{x} \#$y \#@z #x""#
+        );
+        assert_eq!(inspect("héllo 日本").as_str(), "\"héllo 日本\"");
+        assert_eq!(
+            inspect("\x00\x7f\u{85}\u{2028}").as_str(),
+            r#""\u0000\u007F\u0085\u2028""#
+        );
+    }
+
+    #[ruby_test]
+    fn test_push_inspect_bytes() {
+        let mut s = RedString::new();
+        s.push_inspect_bytes(b"ok\xffn\xc3", true);
+        assert_eq!(s.as_str(), r#""ok\xFFn\xC3""#);
+
+        let mut s = RedString::new();
+        s.push_inspect_bytes("é\x01".as_bytes(), false);
+        assert_eq!(s.as_str(), r#""\xC3\xA9\x01""#);
+    }
+}
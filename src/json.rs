@@ -0,0 +1,280 @@
+use crate::{RedString, RedVec, RubyAllocator};
+
+struct Frame {
+    map: bool,
+    len: usize,
+    // In a map, whether the next call must be the value for a key.
+    has_key: bool,
+}
+
+// Writes a JSON document call by call, with no tree in between:
+//
+//     let mut json = json::Encoder::new();
+//     json.begin_map();
+//     json.key("id");
+//     json.int(1);
+//     json.end_map();
+//
+// Commas and colons go in automatically. Calls that would produce invalid
+// JSON, like a value in a map without a key or an unbalanced `end_map`,
+// panic. Several top-level values are written one per line, as NDJSON.
+pub struct Encoder {
+    buf: RedString,
+    stack: RedVec<Frame>,
+    values: usize,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::from_buf(RedString::new())
+    }
+
+    pub fn from_buf(buf: RedString) -> Self {
+        Self {
+            buf,
+            stack: RedVec::new_in(RubyAllocator::new()),
+            values: 0,
+        }
+    }
+
+    pub fn begin_map(&mut self) {
+        self.begin_value();
+        self.buf.push('{');
+        self.stack.push(Frame {
+            map: true,
+            len: 0,
+            has_key: false,
+        });
+    }
+
+    pub fn end_map(&mut self) {
+        self.end(true);
+        self.buf.push('}');
+    }
+
+    pub fn begin_array(&mut self) {
+        self.begin_value();
+        self.buf.push('[');
+        self.stack.push(Frame {
+            map: false,
+            len: 0,
+            has_key: false,
+        });
+    }
+
+    pub fn end_array(&mut self) {
+        self.end(false);
+        self.buf.push(']');
+    }
+
+    pub fn key(&mut self, key: &str) {
+        let frame = match self.stack.last_mut() {
+            Some(frame) if frame.map && !frame.has_key => frame,
+            _ => panic!("JSON key outside a map or in place of a value"),
+        };
+        frame.has_key = true;
+        if frame.len > 0 {
+            self.buf.push(',');
+        }
+        self.buf.push_json_escaped(key);
+        self.buf.push(':');
+    }
+
+    pub fn str(&mut self, s: &str) {
+        self.begin_value();
+        self.buf.push_json_escaped(s);
+    }
+
+    pub fn int(&mut self, n: i64) {
+        self.begin_value();
+        write_int(&mut self.buf, n);
+    }
+
+    pub fn uint(&mut self, n: u64) {
+        self.begin_value();
+        write_int(&mut self.buf, n);
+    }
+
+    // JSON has no NaN or infinities, so those are written as `null`, like
+    // JavaScript's `JSON.stringify`.
+    pub fn float(&mut self, f: f64) {
+        self.begin_value();
+        if f.is_finite() {
+            write_float(&mut self.buf, f);
+        } else {
+            self.buf.push_str("null");
+        }
+    }
+
+    pub fn bool(&mut self, b: bool) {
+        self.begin_value();
+        self.buf.push_str(if b { "true" } else { "false" });
+    }
+
+    pub fn null(&mut self) {
+        self.begin_value();
+        self.buf.push_str("null");
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.buf.as_str()
+    }
+
+    // Panics if a map or array is still open.
+    pub fn into_inner(self) -> RedString {
+        assert!(self.stack.is_empty(), "JSON map or array left open");
+        self.buf
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        self.into_inner().into_rstring()
+    }
+
+    fn begin_value(&mut self) {
+        let Some(frame) = self.stack.last_mut() else {
+            if self.values > 0 {
+                self.buf.push('\n');
+            }
+            self.values += 1;
+            return;
+        };
+        if frame.map {
+            assert!(frame.has_key, "JSON value in a map without a key");
+            frame.has_key = false;
+        } else if frame.len > 0 {
+            self.buf.push(',');
+        }
+        frame.len += 1;
+    }
+
+    fn end(&mut self, map: bool) {
+        match self.stack.pop() {
+            Some(frame) if frame.map == map && !frame.has_key => {}
+            _ => panic!("unbalanced JSON end_{}", if map { "map" } else { "array" }),
+        }
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RedString {
+    // Appends `s` as a quoted JSON string. Only `"`, `\` and control
+    // characters are escaped; everything else, including non-ASCII, is
+    // copied as is.
+    pub fn push_json_escaped(&mut self, s: &str) {
+        self.reserve(s.len() + 2);
+        self.push('"');
+        let mut start = 0;
+        for (idx, byte) in s.bytes().enumerate() {
+            let escape = match byte {
+                b'"' => "\\\"",
+                b'\\' => "\\\\",
+                b'\n' => "\\n",
+                b'\r' => "\\r",
+                b'\t' => "\\t",
+                0x08 => "\\b",
+                0x0c => "\\f",
+                0x00..=0x1f => "",
+                _ => continue,
+            };
+            self.push_str(&s[start..idx]);
+            if escape.is_empty() {
+                const HEX: &[u8; 16] = b"0123456789abcdef";
+                self.push_str("\\u00");
+                self.push(HEX[(byte >> 4) as usize] as char);
+                self.push(HEX[(byte & 0xf) as usize] as char);
+            } else {
+                self.push_str(escape);
+            }
+            start = idx + 1;
+        }
+        self.push_str(&s[start..]);
+        self.push('"');
+    }
+}
+
+fn write_int(buf: &mut RedString, n: impl std::fmt::Display) {
+    use std::fmt::Write;
+
+    write!(buf, "{}", n).unwrap();
+}
+
+// `Debug` always keeps a decimal point or exponent, so whole floats don't
+// read back as integers.
+fn write_float(buf: &mut RedString, f: f64) {
+    use std::fmt::Write;
+
+    write!(buf, "{:?}", f).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::Encoder;
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_encoder() {
+        let mut json = Encoder::new();
+        json.begin_map();
+        json.key("id");
+        json.int(-1);
+        json.key("tags");
+        json.begin_array();
+        json.str("a");
+        json.uint(u64::MAX);
+        json.float(1.0);
+        json.float(f64::NAN);
+        json.begin_map();
+        json.end_map();
+        json.end_array();
+        json.key("ok");
+        json.bool(true);
+        json.key("none");
+        json.null();
+        json.end_map();
+        assert_eq!(
+            json.into_inner().as_str(),
+            r#"{"id":-1,"tags":["a",18446744073709551615,1.0,null,{}],"ok":true,"none":null}"#
+        );
+    }
+
+    #[ruby_test]
+    fn test_escaping() {
+        let mut s = RedString::new();
+        s.push_json_escaped("q\"b\\n\n\u{1}é/");
+        assert_eq!(s.as_str(), r#""q\"b\\n\n\u0001é/""#);
+    }
+
+    #[ruby_test]
+    fn test_top_level_values() {
+        let mut json = Encoder::new();
+        json.int(1);
+        json.int(2);
+        json.begin_array();
+        json.end_array();
+        assert_eq!(json.into_inner().as_str(), "1\n2\n[]");
+    }
+
+    #[ruby_test]
+    #[should_panic]
+    fn test_value_without_key() {
+        let mut json = Encoder::new();
+        json.begin_map();
+        json.int(1);
+    }
+
+    #[ruby_test]
+    #[should_panic]
+    fn test_unbalanced() {
+        let mut json = Encoder::new();
+        json.begin_array();
+        json.end_map();
+    }
+}
@@ -0,0 +1,212 @@
+use magnus::{
+    prelude::*,
+    rb_sys::{AsRawValue, FromRawValue},
+};
+
+use crate::{RedString, EMBED_LEN_MAX};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyKind {
+    Symbol,
+    String,
+}
+
+// A hash key already in the form Ruby stores it, so inserting it costs no
+// conversion. `Hash#[]=` copies and freezes an unfrozen String key, which
+// frozen keys skip.
+#[derive(Clone, Copy)]
+pub enum RedKey {
+    // From the symbol table; symbols made from a name are never collected.
+    Symbol(magnus::Symbol),
+    // From Ruby's table of frozen strings, shared with equal literals.
+    Interned(magnus::RString),
+    // A new frozen string, for keys unlikely to repeat.
+    Fresh(magnus::RString),
+}
+
+impl RedKey {
+    pub fn symbol(name: &str) -> Self {
+        let raw = unsafe {
+            let id = rb_sys::rb_intern3(
+                name.as_ptr() as *const i8,
+                name.len().try_into().unwrap(),
+                rb_sys::rb_utf8_encoding(),
+            );
+            rb_sys::rb_id2sym(id)
+        };
+        RedKey::Symbol(magnus::Symbol::from_value(unsafe { magnus::Value::from_raw(raw) }).unwrap())
+    }
+
+    pub fn interned(s: &str) -> Self {
+        let raw = unsafe {
+            let raw = rb_sys::rb_enc_interned_str(
+                s.as_ptr() as *const i8,
+                s.len().try_into().unwrap(),
+                rb_sys::rb_utf8_encoding(),
+            );
+            crate::set_coderange(raw, s.is_ascii());
+            raw
+        };
+        RedKey::Interned(rstring(raw))
+    }
+
+    pub fn fresh(s: RedString) -> Self {
+        let raw = unsafe { rb_sys::rb_obj_freeze(s.into_raw_rstring()) };
+        RedKey::Fresh(rstring(raw))
+    }
+
+    // Short string keys are usually field names that repeat across many
+    // hashes, and interning one is a table lookup that allocates nothing
+    // after the first time. Longer ones are more likely to be unique data
+    // that would only grow the table.
+    pub fn string(s: &str) -> Self {
+        if s.len() <= EMBED_LEN_MAX {
+            Self::interned(s)
+        } else {
+            Self::fresh(RedString::from_str(s))
+        }
+    }
+
+    pub fn new(kind: KeyKind, s: &str) -> Self {
+        match kind {
+            KeyKind::Symbol => Self::symbol(s),
+            KeyKind::String => Self::string(s),
+        }
+    }
+
+    pub fn as_value(self) -> magnus::Value {
+        match self {
+            RedKey::Symbol(sym) => sym.as_value(),
+            RedKey::Interned(s) | RedKey::Fresh(s) => s.as_value(),
+        }
+    }
+}
+
+fn rstring(raw: rb_sys::VALUE) -> magnus::RString {
+    magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw) }).unwrap()
+}
+
+const BATCH: usize = 64;
+
+// Builds an `RHash` from Rust-side keys, batching entries into
+// `rb_hash_bulk_insert` calls. Pending entries are kept in a Ruby array so
+// the GC sees them; like any `magnus::Value`, the builder must stay on the
+// stack.
+pub struct RHashBuilder {
+    hash: magnus::RHash,
+    pending: magnus::RArray,
+    kind: KeyKind,
+}
+
+impl RHashBuilder {
+    pub fn new(kind: KeyKind) -> Self {
+        Self::with_capacity(kind, 0)
+    }
+
+    pub fn with_capacity(kind: KeyKind, capacity: usize) -> Self {
+        let hash = unsafe { rb_sys::rb_hash_new_capa(capacity.try_into().unwrap()) };
+        let pending =
+            unsafe { rb_sys::rb_ary_new_capa((2 * capacity.min(BATCH)).try_into().unwrap()) };
+        Self {
+            hash: magnus::RHash::from_value(unsafe { magnus::Value::from_raw(hash) }).unwrap(),
+            pending: magnus::RArray::from_value(unsafe { magnus::Value::from_raw(pending) })
+                .unwrap(),
+            kind,
+        }
+    }
+
+    pub fn insert<V: ReprValue>(&mut self, key: &str, value: V) -> Result<(), magnus::Error> {
+        self.insert_key(RedKey::new(self.kind, key), value)
+    }
+
+    pub fn insert_key<V: ReprValue>(&mut self, key: RedKey, value: V) -> Result<(), magnus::Error> {
+        unsafe {
+            rb_sys::rb_ary_push(self.pending.as_raw(), key.as_value().as_raw());
+            rb_sys::rb_ary_push(self.pending.as_raw(), value.as_value().as_raw());
+        }
+        if self.pending.len() >= 2 * BATCH {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<magnus::RHash, magnus::Error> {
+        self.flush()?;
+        Ok(self.hash)
+    }
+
+    fn flush(&mut self) -> Result<(), magnus::Error> {
+        // Nothing runs between reading the array's storage and inserting
+        // it, so it can't move or change underneath us.
+        self.hash.bulk_insert(unsafe { self.pending.as_slice() })?;
+        unsafe { rb_sys::rb_ary_clear(self.pending.as_raw()) };
+        Ok(())
+    }
+}
+
+impl<K, V> Extend<(K, V)> for RHashBuilder
+where
+    K: AsRef<str>,
+    V: ReprValue,
+{
+    // Panics if Ruby raises while inserting; use `insert` to handle that.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key.as_ref(), value).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::{eval, prelude::*, rb_sys::AsRawValue, Integer, RHash};
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{KeyKind, RHashBuilder, RedKey};
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_key_kinds() {
+        assert!(matches!(RedKey::string("id"), RedKey::Interned(_)));
+        let long = "k".repeat(100);
+        let RedKey::Fresh(fresh) = RedKey::string(&long) else {
+            panic!("expected a fresh key");
+        };
+        assert!(fresh.is_frozen());
+
+        let RedKey::Interned(a) = RedKey::interned("name") else {
+            unreachable!()
+        };
+        let RedKey::Interned(b) = RedKey::interned("name") else {
+            unreachable!()
+        };
+        assert_eq!(a.as_raw(), b.as_raw());
+
+        let sym = RedKey::symbol("name").as_value();
+        assert!(sym.eql(eval::<magnus::Value>(":name").unwrap()).unwrap());
+        assert!(RedKey::fresh(RedString::from_str("x"))
+            .as_value()
+            .is_frozen());
+    }
+
+    #[ruby_test]
+    fn test_builder() {
+        let mut builder = RHashBuilder::new(KeyKind::Symbol);
+        for i in 0..200 {
+            builder
+                .insert(&format!("k{}", i), Integer::from_i64(i))
+                .unwrap();
+        }
+        builder.extend([("k0", Integer::from_i64(-1))]);
+        let hash: RHash = builder.finish().unwrap();
+        assert_eq!(hash.len(), 200);
+        let k0: i64 = hash.fetch(magnus::Symbol::new("k0")).unwrap();
+        assert_eq!(k0, -1);
+
+        let mut builder = RHashBuilder::with_capacity(KeyKind::String, 1);
+        builder.insert("id", Integer::from_i64(1)).unwrap();
+        let hash = builder.finish().unwrap();
+        let id: i64 = hash.fetch("id").unwrap();
+        assert_eq!(id, 1);
+    }
+}
@@ -1,51 +1,312 @@
+#[cfg(feature = "bytes")]
+mod buf;
+mod builder;
+mod casecmp;
+mod char_index;
+mod chars;
+mod checkpoint;
+#[cfg(feature = "magnus")]
+mod chunked;
+mod cow;
+pub mod csv;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "encoding_rs")]
+mod encoding;
+mod error;
+mod growth;
+mod gvl;
+mod hash;
+mod html;
+#[cfg(feature = "magnus")]
+mod guard;
+mod inspect;
+pub mod json;
+#[cfg(feature = "magnus")]
+mod key;
+#[cfg(feature = "magnus")]
+mod line_writer;
+mod literal;
+#[cfg(feature = "mock-alloc")]
+mod mock;
+pub mod msgpack;
+#[cfg(feature = "logger")]
+mod logger;
+#[cfg(feature = "aho-corasick")]
+mod multisearch;
+// Inert under `mock-alloc`, which never calls into Ruby to allocate.
+#[cfg(feature = "nogvl")]
+#[cfg_attr(feature = "mock-alloc", allow(dead_code))]
+mod nogvl;
+mod pack;
+#[cfg(feature = "magnus")]
+mod pinned;
+mod pool;
+#[cfg(feature = "protected")]
+#[cfg_attr(feature = "mock-alloc", allow(dead_code))]
+mod protect;
+mod redbytes;
+#[cfg(feature = "magnus")]
+mod rstring;
+#[cfg(feature = "magnus")]
+mod ruby_io;
+mod scratch;
+mod search;
+mod set;
+mod sharded;
+pub mod size_hint;
+#[cfg(feature = "magnus")]
+mod snapshot;
+// Relies on unlinking the temp file while it is still open.
+#[cfg(unix)]
+mod spill;
+mod splice;
+#[cfg(feature = "stats")]
+pub mod stats;
+mod streaming;
+#[cfg(feature = "magnus")]
+pub mod transcode;
+#[cfg(feature = "unicode")]
+mod unicode;
+#[cfg(feature = "width")]
+mod width;
+
+#[cfg(feature = "bytes")]
+pub use buf::RedBytesReader;
+pub use builder::RedStringBuilder;
+pub use char_index::CharIndex;
+pub use chars::{CharIndicesMut, RedChars, RedDrain};
+pub use checkpoint::{Checkpoint, Mark};
+#[cfg(feature = "magnus")]
+pub use cow::FrozenRString;
+pub use cow::RedCow;
+#[cfg(feature = "serde")]
+pub use de::{from_value, DeError, Deserializer};
+#[cfg(feature = "encoding_rs")]
+pub use encoding::{EncodingError, ForeignDecoder, Malformed};
+pub use error::RedError;
+pub use growth::{growth, set_growth, with_growth, Growth};
+#[cfg(feature = "magnus")]
+pub use guard::RStringMutGuard;
+pub use gvl::{GvlBound, GvlSend};
+pub use hash::{RubyBuildHasher, RubyHasher};
+#[cfg(feature = "magnus")]
+pub use key::{KeyKind, RHashBuilder, RedKey};
+#[cfg(feature = "magnus")]
+pub use line_writer::{Newline, RedLineWriter};
+#[cfg(feature = "magnus")]
+pub use literal::{register_literals, LiteralCache, LiteralTable};
+#[cfg(feature = "logger")]
+pub use logger::RubyLogWriter;
+#[cfg(feature = "tracing")]
+pub use logger::RubyMakeWriter;
+pub use pack::Slot;
+#[cfg(feature = "magnus")]
+pub use pinned::Pinned;
+pub use pool::{PooledString, RedStringPool};
+pub use redbytes::RedBytes;
+pub use scratch::ScratchBuffer;
+pub use set::RedStringSet;
+pub use sharded::{Shard, ShardedBuilder};
+pub use size_hint::SizeHint;
+#[cfg(feature = "magnus")]
+pub use snapshot::SnapshotBuilder;
+#[cfg(unix)]
+pub use spill::SpillBuilder;
+pub use streaming::{DecodeError, StreamingDecoder};
+
 use std::ops::{Deref, DerefMut};
 
-use magnus::rb_sys::FromRawValue;
 
-struct RubyAllocator {}
+// Not `Send` or `Sync`: every container using it calls into Ruby when it
+// grows or is dropped, which is only allowed while holding the GVL. Use
+// `GvlBound` to move one to another Ruby thread. The `mock-alloc` feature
+// replaces Ruby's allocator with the system one, for testing without a VM.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RubyAllocator {
+    _not_send: std::marker::PhantomData<*mut ()>,
+}
 
 unsafe impl allocator_api2::alloc::Allocator for RubyAllocator {
     fn allocate(
         &self,
         layout: std::alloc::Layout,
     ) -> Result<std::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
-        let ptr = unsafe {
-            rb_sys::ruby_xmalloc(
-                layout
-                    .size().try_into().map_err(|_| allocator_api2::alloc::AllocError)?
-            )
-        };
+        #[cfg(feature = "mock-alloc")]
+        let ptr = mock::allocate(layout)?;
+        #[cfg(not(feature = "mock-alloc"))]
+        let ptr = Self::allocate_ruby(layout)?;
+        #[cfg(feature = "stats")]
+        stats::record_alloc(layout.size());
         Ok(std::ptr::NonNull::slice_from_raw_parts(
             unsafe { std::ptr::NonNull::new_unchecked(ptr as *mut u8) },
             layout.size(),
         ))
     }
 
-    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, _: std::alloc::Layout) {
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, _layout: std::alloc::Layout) {
+        #[cfg(feature = "mock-alloc")]
+        mock::deallocate(ptr.as_ptr() as *mut libc::c_void, _layout);
+        #[cfg(all(feature = "nogvl", not(feature = "mock-alloc")))]
+        nogvl::deallocate(ptr.as_ptr() as *mut libc::c_void);
+        #[cfg(not(any(feature = "nogvl", feature = "mock-alloc")))]
         rb_sys::ruby_xfree(ptr.as_ptr() as *mut libc::c_void);
+        #[cfg(feature = "stats")]
+        stats::record_dealloc(_layout.size());
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+        self.reallocate(ptr, old_layout, new_layout)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+        self.reallocate(ptr, old_layout, new_layout)
     }
 }
 
+impl RubyAllocator {
+    pub const fn new() -> Self {
+        Self {
+            _not_send: std::marker::PhantomData,
+        }
+    }
+
+    #[cfg(not(feature = "mock-alloc"))]
+    fn allocate_ruby(
+        layout: std::alloc::Layout,
+    ) -> Result<*mut libc::c_void, allocator_api2::alloc::AllocError> {
+        let size = layout
+            .size()
+            .try_into()
+            .map_err(|_| allocator_api2::alloc::AllocError)?;
+        let ruby = || Self::call(|| unsafe { rb_sys::ruby_xmalloc(size) });
+        #[cfg(feature = "nogvl")]
+        return nogvl::allocate(layout.size(), ruby);
+        #[cfg(not(feature = "nogvl"))]
+        ruby()
+    }
+
+    unsafe fn reallocate(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        _old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, allocator_api2::alloc::AllocError> {
+        #[cfg(feature = "mock-alloc")]
+        let new_ptr = mock::reallocate(ptr.as_ptr() as *mut libc::c_void, _old_layout, new_layout)?;
+        #[cfg(not(feature = "mock-alloc"))]
+        let new_ptr = Self::reallocate_ruby(ptr, _old_layout, new_layout)?;
+        #[cfg(feature = "stats")]
+        stats::record_realloc(_old_layout.size(), new_layout.size());
+        Ok(std::ptr::NonNull::slice_from_raw_parts(
+            std::ptr::NonNull::new_unchecked(new_ptr as *mut u8),
+            new_layout.size(),
+        ))
+    }
+
+    #[cfg(not(feature = "mock-alloc"))]
+    unsafe fn reallocate_ruby(
+        ptr: std::ptr::NonNull<u8>,
+        _old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<*mut libc::c_void, allocator_api2::alloc::AllocError> {
+        let size = new_layout
+            .size()
+            .try_into()
+            .map_err(|_| allocator_api2::alloc::AllocError)?;
+        // on failure ruby_xrealloc leaves the old buffer untouched, so the
+        // container is still in its pre-growth state
+        let ruby =
+            || Self::call(|| rb_sys::ruby_xrealloc(ptr.as_ptr() as *mut libc::c_void, size));
+        #[cfg(feature = "nogvl")]
+        return nogvl::reallocate(
+            ptr.as_ptr() as *mut libc::c_void,
+            _old_layout.size(),
+            new_layout.size(),
+            ruby,
+        );
+        #[cfg(not(feature = "nogvl"))]
+        ruby()
+    }
+
+    // A failed allocation raises `NoMemoryError` and never returns here,
+    // unless the `protected` feature is on and a `try_*` method asked for it.
+    #[cfg_attr(feature = "mock-alloc", allow(dead_code))]
+    fn call(
+        f: impl FnOnce() -> *mut libc::c_void,
+    ) -> Result<*mut libc::c_void, allocator_api2::alloc::AllocError> {
+        #[cfg(feature = "protected")]
+        if protect::is_fallible() {
+            return protect::protect(f).ok_or(allocator_api2::alloc::AllocError);
+        }
+        Ok(f())
+    }
+}
+
+// `RSTRING_EMBED_LEN_MAX` on 64-bit: strings up to this length always fit
+// inside the object slot, so creating one needs no separate heap buffer.
+// Ruby 3.2+ can embed longer strings in larger slots, but not reliably.
+pub(crate) const EMBED_LEN_MAX: usize = 3 * std::mem::size_of::<rb_sys::VALUE>() - 1;
+
+// Ruby scans a string the first time it needs its coderange, which core
+// methods like `length`, `==` and regex matching do. Our buffers are always
+// valid for their encoding, so callers only have to find out whether the
+// bytes are ASCII, which is much cheaper than Ruby's scan. Must be called
+// after the encoding is set, since associating one may clear the coderange.
+pub(crate) unsafe fn set_coderange(raw: rb_sys::VALUE, ascii: bool) {
+    use rb_sys::ruby_coderange_type::*;
+
+    let coderange = if ascii {
+        RUBY_ENC_CODERANGE_7BIT
+    } else {
+        RUBY_ENC_CODERANGE_VALID
+    };
+    let basic = raw as *mut rb_sys::RBasic;
+    (*basic).flags =
+        (*basic).flags & !(RUBY_ENC_CODERANGE_MASK as rb_sys::VALUE) | coderange as rb_sys::VALUE;
+}
+
+// For bytes changed behind Ruby's back; it scans again on next use.
+pub(crate) unsafe fn clear_coderange(raw: rb_sys::VALUE) {
+    let basic = raw as *mut rb_sys::RBasic;
+    (*basic).flags &= !(rb_sys::ruby_coderange_type::RUBY_ENC_CODERANGE_MASK as rb_sys::VALUE);
+}
+
+// `RedVec::new_in(RubyAllocator::new())` is const, like `RedString::new`.
+pub type RedVec<T> = allocator_api2::vec::Vec<T, RubyAllocator>;
+
 pub struct RedString {
     buf: allocator_api2::vec::Vec<u8, RubyAllocator>,
 }
 
 impl RedString {
-    pub fn new() -> Self {
+    // Allocates nothing until the first push, so it can initialize a
+    // `thread_local!` directly; see `ScratchBuffer`.
+    pub const fn new() -> Self {
         Self {
-            buf: allocator_api2::vec::Vec::new_in(RubyAllocator {}),
+            buf: allocator_api2::vec::Vec::new_in(RubyAllocator::new()),
         }
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            buf: allocator_api2::vec::Vec::with_capacity_in(capacity, RubyAllocator {}),
+            buf: allocator_api2::vec::Vec::with_capacity_in(capacity, RubyAllocator::new()),
         }
     }
 
     pub fn from_str(s: &str) -> Self {
         let mut result = Self {
-            buf: allocator_api2::vec::Vec::with_capacity_in(s.len(), RubyAllocator {}),
+            buf: allocator_api2::vec::Vec::with_capacity_in(s.len(), RubyAllocator::new()),
         };
 
 
@@ -54,7 +315,32 @@ impl RedString {
         result
     }
 
+    /// Takes ownership of a buffer allocated by Ruby's allocator.
+    ///
+    /// # Safety
+    ///
+    /// Same contract as [`RedBytes::from_raw_parts`], and additionally the
+    /// first `len` bytes must be valid UTF-8.
+    pub unsafe fn from_raw_parts(ptr: *mut u8, len: usize, capacity: usize) -> Self {
+        Self {
+            buf: RedBytes::from_raw_parts(ptr, len, capacity).buf,
+        }
+    }
+
+    /// Releases the buffer as `(ptr, len, capacity)`, see
+    /// [`RedBytes::into_raw_parts`].
+    pub fn into_raw_parts(self) -> (*mut u8, usize, usize) {
+        RedBytes { buf: self.buf }.into_raw_parts()
+    }
+
+    pub fn repeat(s: &str, n: usize) -> Self {
+        let mut result = Self::with_capacity(s.len().checked_mul(n).expect("capacity overflow"));
+        result.push_repeat(s, n);
+        result
+    }
+
     pub fn push(&mut self, c: char) {
+        growth::reserve(&mut self.buf, c.len_utf8());
         match c.len_utf8() {
             1 => self.buf.push(c as u8),
             _ => self
@@ -64,27 +350,93 @@ impl RedString {
     }
 
     pub fn push_str(&mut self, s: &str) {
+        growth::reserve(&mut self.buf, s.len());
         self.buf.extend_from_slice(s.as_bytes());
     }
 
+    // Grows by the current `Growth` policy; see `growth`.
+    pub fn reserve(&mut self, additional: usize) {
+        growth::reserve(&mut self.buf, additional);
+    }
+
+    // Ignores the policy and makes room for exactly `additional` more bytes.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.buf.reserve_exact(additional);
+    }
+
+    // With the `protected` feature these also report Ruby allocation
+    // failures instead of raising.
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), allocator_api2::alloc::AllocError> {
+        growth::try_reserve(&mut self.buf, additional)
+    }
+
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), allocator_api2::alloc::AllocError> {
+        self.try_reserve(s.len())?;
+        self.push_str(s);
+        Ok(())
+    }
+
+    pub fn push_repeat(&mut self, s: &str, n: usize) {
+        if n == 0 || s.is_empty() {
+            return;
+        }
+
+        let total = s.len().checked_mul(n).expect("capacity overflow");
+        growth::reserve(&mut self.buf, total);
+        let start = self.len();
+        self.buf.extend_from_slice(s.as_bytes());
+
+        // keep doubling what has been written so far until it covers `total`
+        let mut filled = s.len();
+        unsafe {
+            let ptr = self.buf.as_mut_ptr().add(start);
+            while filled < total {
+                let amt = filled.min(total - filled);
+                std::ptr::copy_nonoverlapping(ptr, ptr.add(filled), amt);
+                filled += amt;
+            }
+            self.buf.set_len(start + total);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.buf.clear();
     }
 
     pub fn insert(&mut self, idx: usize, c: char) {
-        unsafe {
-            self.insert_bytes(idx, c.encode_utf8(&mut [0; 4]).as_bytes());
-        }
+        self.insert_str(idx, c.encode_utf8(&mut [0; 4]));
     }
 
     pub fn insert_str(&mut self, idx: usize, s: &str) {
+        assert!(
+            self.is_char_boundary(idx),
+            "insertion index is not a char boundary"
+        );
+        unsafe { self.insert_bytes(idx, s.as_bytes()) };
+    }
+
+    pub fn try_insert(&mut self, idx: usize, c: char) -> Result<(), RedError> {
+        self.try_insert_str(idx, c.encode_utf8(&mut [0; 4]))
+    }
+
+    pub fn try_insert_str(&mut self, idx: usize, s: &str) -> Result<(), RedError> {
+        self.check_boundary(idx)?;
+        self.try_reserve(s.len())?;
         unsafe { self.insert_bytes(idx, s.as_bytes()) };
+        Ok(())
     }
 
     pub fn len(&self) -> usize {
         self.buf.len()
     }
 
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
     pub fn as_str(&self) -> &str {
         unsafe { std::str::from_utf8_unchecked(&self.buf) }
     }
@@ -112,6 +464,14 @@ impl RedString {
         ch
     }
 
+    pub fn try_remove(&mut self, idx: usize) -> Result<char, RedError> {
+        if idx == self.len() {
+            return Err(self.bounds_error(idx));
+        }
+        self.check_boundary(idx)?;
+        Ok(self.remove(idx))
+    }
+
     pub fn pop(&mut self) -> Option<char> {
         let ch = self.chars().rev().next()?;
         let newlen = self.len() - ch.len_utf8();
@@ -121,20 +481,190 @@ impl RedString {
         Some(ch)
     }
 
-    pub fn into_rstring(self) -> magnus::RString {
-        let raw_value = unsafe {
-            rb_sys::rb_utf8_str_new(self.buf.as_ptr() as *const i8, self.buf.len().try_into().unwrap())
-        };
+    pub fn trim_in_place(&mut self) {
+        self.trim_end_in_place();
+        self.trim_start_in_place();
+    }
+
+    pub fn trim_start_in_place(&mut self) {
+        let amt = self.len() - self.trim_start().len();
+        self.remove_prefix(amt);
+    }
+
+    pub fn trim_end_in_place(&mut self) {
+        let newlen = self.trim_end().len();
+        self.buf.truncate(newlen);
+    }
+
+    pub fn strip_prefix_in_place(&mut self, prefix: &str) -> bool {
+        if !self.starts_with(prefix) {
+            return false;
+        }
+        self.remove_prefix(prefix.len());
+        true
+    }
+
+    pub fn replace_in_place(&mut self, from: &str, to: &str) {
+        self.replacen_in_place(from, to, usize::MAX);
+    }
+
+    pub fn replacen_in_place(&mut self, from: &str, to: &str, count: usize) {
+        if count == 0 || (from.is_empty() && to.is_empty()) {
+            return;
+        }
+
+        if to.len() <= from.len() {
+            unsafe { self.replace_shrinking(from, to, count) }
+        } else {
+            self.replace_growing(from, to, count)
+        }
+    }
+
+    pub fn make_ascii_uppercase(&mut self) {
+        self.buf.make_ascii_uppercase();
+    }
+
+    pub fn make_ascii_lowercase(&mut self) {
+        self.buf.make_ascii_lowercase();
+    }
+
+    pub fn to_ascii_uppercase(&self) -> Self {
+        let mut result = Self::from_str(self.as_str());
+        result.make_ascii_uppercase();
+        result
+    }
+
+    pub fn to_ascii_lowercase(&self) -> Self {
+        let mut result = Self::from_str(self.as_str());
+        result.make_ascii_lowercase();
+        result
+    }
+
+    // Ruby has no way to adopt a buffer it didn't allocate as a string, so the
+    // bytes are copied either way; short strings end up embedded in the
+    // object. Our buffer is freed as soon as the copy is made.
+    pub fn into_raw_rstring(self) -> rb_sys::VALUE {
+        self.clone_to_raw_rstring()
+    }
+
+    pub fn clone_to_raw_rstring(&self) -> rb_sys::VALUE {
+        unsafe {
+            let raw_value = rb_sys::rb_utf8_str_new(
+                self.buf.as_ptr() as *const i8,
+                self.buf.len().try_into().unwrap(),
+            );
+            set_coderange(raw_value, self.buf.is_ascii());
+            raw_value
+        }
+    }
+
+    pub fn reader(&self) -> std::io::Cursor<&[u8]> {
+        std::io::Cursor::new(self.as_bytes())
+    }
+
+    pub fn into_reader(self) -> std::io::Cursor<Self> {
+        std::io::Cursor::new(self)
+    }
 
-        std::mem::forget(self);
+    pub fn split_owned(&self, pattern: &str) -> RedVec<Self> {
+        let mut pieces = RedVec::new_in(RubyAllocator::new());
+        pieces.extend(self.split(pattern).map(Self::from_str));
+        pieces
+    }
+
+    // The write cursor never overtakes the read cursor, so everything from
+    // `read` onwards is still the original content and can be searched.
+    unsafe fn replace_shrinking(&mut self, from: &str, to: &str, count: usize) {
+        let len = self.len();
+        let ptr = self.buf.as_mut_ptr();
+        let mut read = 0;
+        let mut write = 0;
+
+        for _ in 0..count {
+            let rest = std::str::from_utf8_unchecked(std::slice::from_raw_parts(
+                ptr.add(read),
+                len - read,
+            ));
+            let Some(idx) = rest.find(from) else {
+                break;
+            };
+
+            std::ptr::copy(ptr.add(read), ptr.add(write), idx);
+            write += idx;
+            std::ptr::copy_nonoverlapping(to.as_ptr(), ptr.add(write), to.len());
+            write += to.len();
+            read += idx + from.len();
+        }
 
-        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+        std::ptr::copy(ptr.add(read), ptr.add(write), len - read);
+        self.buf.set_len(write + len - read);
+    }
+
+    // Matches are found front to back (so overlapping patterns behave like
+    // `str::replace`), then the buffer is rewritten back to front after a
+    // single reserve so nothing is overwritten before it has been moved.
+    // Both allocations happen before anything is modified or needs dropping,
+    // so an allocation failure raising out of here leaves `self` untouched and
+    // leaks nothing.
+    fn replace_growing(&mut self, from: &str, to: &str, count: usize) {
+        let matches = self.match_indices(from).take(count).count();
+        if matches == 0 {
+            return;
+        }
+
+        let len = self.len();
+        let new_len = len + matches * (to.len() - from.len());
+        growth::reserve(&mut self.buf, new_len - len);
+        let mut positions = RedVec::with_capacity_in(matches, RubyAllocator::new());
+        positions.extend(self.match_indices(from).take(count).map(|(idx, _)| idx));
+
+        unsafe {
+            let ptr = self.buf.as_mut_ptr();
+            let mut src_end = len;
+            let mut dst_end = new_len;
+            for &idx in positions.iter().rev() {
+                let tail = idx + from.len();
+                dst_end -= src_end - tail;
+                std::ptr::copy(ptr.add(tail), ptr.add(dst_end), src_end - tail);
+                dst_end -= to.len();
+                std::ptr::copy_nonoverlapping(to.as_ptr(), ptr.add(dst_end), to.len());
+                src_end = idx;
+            }
+            self.buf.set_len(new_len);
+        }
+    }
+
+    // `amt` must be on a char boundary.
+    fn remove_prefix(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        let len = self.len();
+        unsafe {
+            std::ptr::copy(self.buf.as_ptr().add(amt), self.buf.as_mut_ptr(), len - amt);
+            self.buf.set_len(len - amt);
+        }
+    }
+
+    fn check_boundary(&self, idx: usize) -> Result<(), RedError> {
+        if self.is_char_boundary(idx) {
+            Ok(())
+        } else {
+            Err(self.bounds_error(idx))
+        }
+    }
+
+    fn bounds_error(&self, idx: usize) -> RedError {
+        RedError::Bounds {
+            index: idx,
+            len: self.len(),
+        }
     }
 
     unsafe fn insert_bytes(&mut self, idx: usize, bytes: &[u8]) {
         let len = self.len();
         let amt = bytes.len();
-        self.buf.reserve(amt);
+        growth::reserve(&mut self.buf, amt);
 
         std::ptr::copy(
             self.buf.as_ptr().add(idx),
@@ -160,6 +690,49 @@ impl DerefMut for RedString {
     }
 }
 
+impl PartialEq for RedString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for RedString {}
+
+// Must hash like `str` so maps keyed by `RedString` can be queried with `&str`.
+impl std::hash::Hash for RedString {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl std::borrow::Borrow<str> for RedString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for RedString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for RedString {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+// Reuses the buffer, so nothing is copied once the bytes check out.
+impl TryFrom<RedBytes> for RedString {
+    type Error = RedError;
+
+    fn try_from(bytes: RedBytes) -> Result<Self, Self::Error> {
+        std::str::from_utf8(bytes.as_slice())?;
+        Ok(Self { buf: bytes.buf })
+    }
+}
+
 impl std::fmt::Write for RedString {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
         self.push_str(s);
@@ -196,6 +769,50 @@ mod tests {
         assert_eq!(s.as_str(), "abc");
     }
 
+    #[ruby_test]
+    fn test_try_push_str() {
+        let mut s = super::RedString::new();
+        s.try_push_str("hello").unwrap();
+        assert_eq!(s.as_str(), "hello");
+        assert!(s.try_reserve(usize::MAX).is_err());
+        assert_eq!(s.as_str(), "hello");
+    }
+
+    // Big enough for Ruby's malloc to fail, but not for `Vec` to reject it as
+    // a capacity overflow before the allocator is even called.
+    #[cfg(all(feature = "protected", feature = "magnus", not(feature = "mock-alloc")))]
+    #[ruby_test]
+    fn test_protected_alloc_failure() {
+        let mut s = super::RedString::from_str("hello");
+        assert!(s.try_reserve(1 << 62).is_err());
+        assert_eq!(s.as_str(), "hello");
+        assert_eq!(unsafe { rb_sys::rb_errinfo() }, rb_sys::Qnil as rb_sys::VALUE);
+
+        // Infallible growth raises rather than aborting in `Vec`.
+        let err = magnus::rb_sys::protect(|| {
+            s.reserve(1 << 62);
+            rb_sys::Qnil as rb_sys::VALUE
+        })
+        .unwrap_err();
+        assert!(err.is_kind_of(magnus::exception::no_mem_error()));
+        assert_eq!(s.as_str(), "hello");
+    }
+
+    #[ruby_test]
+    fn test_repeat() {
+        let s = super::RedString::repeat("ab", 5);
+        assert_eq!(s.as_str(), "ababababab");
+        assert_eq!(s.capacity(), 10);
+        assert_eq!(super::RedString::repeat("ab", 0).as_str(), "");
+    }
+
+    #[ruby_test]
+    fn test_push_repeat() {
+        let mut s = super::RedString::from_str("x:");
+        s.push_repeat("é", 7);
+        assert_eq!(s.as_str(), format!("x:{}", "é".repeat(7)));
+    }
+
     #[ruby_test]
     fn test_insert() {
         let mut s = super::RedString::from_str("abc");
@@ -228,6 +845,87 @@ mod tests {
         assert_eq!(s.as_str(), "ab");
     }
 
+    #[ruby_test]
+    fn test_trim_in_place() {
+        let mut s = super::RedString::from_str("  \thello world\n ");
+        s.trim_end_in_place();
+        assert_eq!(s.as_str(), "  \thello world");
+        s.trim_start_in_place();
+        assert_eq!(s.as_str(), "hello world");
+
+        let mut s = super::RedString::from_str("\u{3000}héllo\u{3000}");
+        s.trim_in_place();
+        assert_eq!(s.as_str(), "héllo");
+
+        let mut s = super::RedString::from_str(" \n ");
+        s.trim_in_place();
+        assert_eq!(s.as_str(), "");
+    }
+
+    #[ruby_test]
+    fn test_strip_prefix_in_place() {
+        let mut s = super::RedString::from_str("key=value");
+        assert!(!s.strip_prefix_in_place("value"));
+        assert!(s.strip_prefix_in_place("key="));
+        assert_eq!(s.as_str(), "value");
+    }
+
+    #[ruby_test]
+    fn test_replace_in_place() {
+        let cases = [
+            ("hello world", "o", "0"),
+            ("hello world", "l", ""),
+            ("hello world", "o", "ooo"),
+            ("hello world", "xyz", "ooo"),
+            ("aaaa", "aa", "b"),
+            ("aaaa", "aa", "bbb"),
+            ("héllo", "é", "e"),
+            ("abc", "", "-"),
+            ("", "", "-"),
+        ];
+        for (input, from, to) in cases {
+            let mut s = super::RedString::from_str(input);
+            s.replace_in_place(from, to);
+            assert_eq!(s.as_str(), input.replace(from, to));
+        }
+    }
+
+    #[ruby_test]
+    fn test_replacen_in_place() {
+        let mut s = super::RedString::from_str("a-b-c-d");
+        s.replacen_in_place("-", "", 2);
+        assert_eq!(s.as_str(), "abc-d");
+
+        let mut s = super::RedString::from_str("a-b-c-d");
+        s.replacen_in_place("-", "--", 2);
+        assert_eq!(s.as_str(), "a--b--c-d");
+    }
+
+    #[ruby_test]
+    fn test_make_ascii_case() {
+        let mut s = super::RedString::from_str("Content-Type: ÄÖ");
+        s.make_ascii_lowercase();
+        assert_eq!(s.as_str(), "content-type: ÄÖ");
+        s.make_ascii_uppercase();
+        assert_eq!(s.as_str(), "CONTENT-TYPE: ÄÖ");
+    }
+
+    #[ruby_test]
+    fn test_to_ascii_case() {
+        let s = super::RedString::from_str("X-Request-Id");
+        assert_eq!(s.to_ascii_lowercase().as_str(), "x-request-id");
+        assert_eq!(s.to_ascii_uppercase().as_str(), "X-REQUEST-ID");
+        assert_eq!(s.as_str(), "X-Request-Id");
+    }
+
+    #[ruby_test]
+    fn test_raw_parts() {
+        let s = super::RedString::from_str("héllo");
+        let (ptr, len, capacity) = s.into_raw_parts();
+        let s = unsafe { super::RedString::from_raw_parts(ptr, len, capacity) };
+        assert_eq!(s.as_str(), "héllo");
+    }
+
     #[ruby_test]
     fn test_drop() {
         let mut s = super::RedString::from_str("abc");
@@ -245,6 +943,32 @@ mod tests {
         assert_eq!(s.as_str(), "abc");
     }
 
+    #[ruby_test]
+    fn test_reader() {
+        use std::io::{BufRead, Read, Seek, SeekFrom};
+
+        let s = super::RedString::from_str("first\nsecond\n");
+        let lines: Vec<_> = s.reader().lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["first", "second"]);
+
+        let mut reader = s.into_reader();
+        reader.seek(SeekFrom::Start(6)).unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "second\n");
+        assert_eq!(reader.into_inner().as_str(), "first\nsecond\n");
+    }
+
+    #[ruby_test]
+    fn test_split_owned() {
+        let mut s = super::RedString::from_str("a,b,,c");
+        let pieces = s.split_owned(",");
+        s.clear();
+        let pieces: Vec<&str> = pieces.iter().map(|piece| piece.as_str()).collect();
+        assert_eq!(pieces, ["a", "b", "", "c"]);
+    }
+
+    #[cfg(feature = "magnus")]
     #[ruby_test]
     fn test_into_rstring() {
         let s = super::RedString::from_str("abc");
@@ -0,0 +1,252 @@
+use crate::{Pinned, RedString};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Newline {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl Newline {
+    fn as_str(self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+enum Sink {
+    Io(Pinned<magnus::Value>),
+    Block(Pinned<magnus::Value>),
+}
+
+// Builds output a line at a time. Every `\n` written, including the one
+// `writeln!` adds, ends a line with the configured `Newline`.
+//
+// With an IO or block set, completed lines are handed over as soon as they
+// are written (or on `flush`, with auto-flush off) while a partial line
+// stays buffered until the rest of it arrives; `flush` sends it as is, and
+// so does dropping the writer. The IO or block is pinned for as long as the
+// writer holds it, so the writer can be stored anywhere.
+pub struct RedLineWriter {
+    buf: RedString,
+    newline: Newline,
+    auto_flush: bool,
+    sink: Option<Sink>,
+}
+
+impl RedLineWriter {
+    pub fn new() -> Self {
+        Self::from_buf(RedString::new())
+    }
+
+    pub fn from_buf(buf: RedString) -> Self {
+        Self {
+            buf,
+            newline: Newline::Lf,
+            auto_flush: true,
+            sink: None,
+        }
+    }
+
+    pub fn set_newline(&mut self, newline: Newline) {
+        self.newline = newline;
+    }
+
+    pub fn set_auto_flush(&mut self, auto_flush: bool) {
+        self.auto_flush = auto_flush;
+    }
+
+    // Completed lines are written to `io` in one `write` per flush.
+    pub fn set_io(&mut self, io: magnus::Value) {
+        self.sink = Some(Sink::Io(Pinned::new(io)));
+    }
+
+    // `block` is called with each completed line, newline included.
+    pub fn set_block(&mut self, block: magnus::Value) {
+        self.sink = Some(Sink::Block(Pinned::new(block)));
+    }
+
+    pub fn push_str(&mut self, s: &str) -> Result<(), magnus::Error> {
+        let mut lines = s.split('\n');
+        if let Some(first) = lines.next() {
+            self.buf.push_str(first);
+        }
+        for line in lines {
+            self.buf.push_str(self.newline.as_str());
+            self.buf.push_str(line);
+        }
+        self.auto_flush()
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<(), magnus::Error> {
+        self.push_str(line)?;
+        self.end_line()
+    }
+
+    pub fn end_line(&mut self) -> Result<(), magnus::Error> {
+        self.buf.push_str(self.newline.as_str());
+        self.auto_flush()
+    }
+
+    // Everything not handed over yet.
+    pub fn as_str(&self) -> &str {
+        self.buf.as_str()
+    }
+
+    // The text after the last completed line.
+    pub fn partial(&self) -> &str {
+        match self.buf.rfind_byte(b'\n') {
+            Some(idx) => &self.buf[idx + 1..],
+            None => &self.buf,
+        }
+    }
+
+    // Drops the IO or block without flushing to it.
+    pub fn into_inner(mut self) -> RedString {
+        self.sink = None;
+        std::mem::replace(&mut self.buf, RedString::new())
+    }
+
+    pub fn flush(&mut self) -> Result<(), magnus::Error> {
+        self.send(true)
+    }
+
+    fn auto_flush(&mut self) -> Result<(), magnus::Error> {
+        if self.auto_flush {
+            self.send(false)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn send(&mut self, include_partial: bool) -> Result<(), magnus::Error> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+        let end = if include_partial {
+            self.buf.len()
+        } else {
+            match self.buf.rfind_byte(b'\n') {
+                Some(idx) => idx + 1,
+                None => return Ok(()),
+            }
+        };
+        if end == 0 {
+            return Ok(());
+        }
+
+        match sink {
+            Sink::Io(io) => {
+                crate::ruby_io::write_str_to_io(io.get(), &self.buf[..end])?;
+                self.buf.remove_prefix(end);
+                Ok(())
+            }
+            Sink::Block(block) => {
+                let mut sent = 0;
+                let mut result = Ok(());
+                for line in self.buf[..end].split_inclusive('\n') {
+                    result = call(block.get(), line);
+                    if result.is_err() {
+                        break;
+                    }
+                    sent += line.len();
+                }
+                // Lines the block already got are gone even if a later one
+                // raised.
+                self.buf.remove_prefix(sent);
+                result
+            }
+        }
+    }
+}
+
+impl Default for RedLineWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Errors from the IO or block are reported as `fmt::Error`; use `push_str`
+// directly to get the Ruby exception.
+impl std::fmt::Write for RedLineWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s).map_err(|_| std::fmt::Error)
+    }
+}
+
+impl Drop for RedLineWriter {
+    fn drop(&mut self) {
+        if self.sink.is_some() {
+            crate::gvl::try_with_gvl(|| {
+                let _ = self.send(true);
+            });
+        }
+    }
+}
+
+fn call(block: magnus::Value, line: &str) -> Result<(), magnus::Error> {
+    use magnus::prelude::*;
+
+    let _: magnus::Value = block.funcall("call", (magnus::RString::new(line),))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use magnus::{eval, prelude::*, RArray, RString};
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{Newline, RedLineWriter};
+
+    #[ruby_test]
+    fn test_newline_policy() {
+        let mut w = RedLineWriter::new();
+        w.set_newline(Newline::CrLf);
+        writeln!(w, "a\nb").unwrap();
+        w.write_line("c").unwrap();
+        write!(w, "d").unwrap();
+        assert_eq!(w.partial(), "d");
+        assert_eq!(w.into_inner().as_str(), "a\r\nb\r\nc\r\nd");
+    }
+
+    #[ruby_test]
+    fn test_auto_flush_to_io() {
+        let io: magnus::Value = eval("require 'stringio'; StringIO.new").unwrap();
+        let out = || -> String {
+            let s: RString = io.funcall("string", ()).unwrap();
+            s.to_string().unwrap()
+        };
+
+        let mut w = RedLineWriter::new();
+        w.set_io(io);
+        write!(w, "{{\"a\":1}}\n{{\"b\"").unwrap();
+        assert_eq!(out(), "{\"a\":1}\n");
+        assert_eq!(w.partial(), "{\"b\"");
+        writeln!(w, ":2}}").unwrap();
+        assert_eq!(out(), "{\"a\":1}\n{\"b\":2}\n");
+        write!(w, "tail").unwrap();
+        drop(w);
+        assert_eq!(out(), "{\"a\":1}\n{\"b\":2}\ntail");
+    }
+
+    #[ruby_test]
+    fn test_block_without_auto_flush() {
+        let lines = RArray::new();
+        let block: magnus::Value = lines.funcall("method", ("<<",)).unwrap();
+
+        let mut w = RedLineWriter::new();
+        w.set_block(block);
+        w.set_auto_flush(false);
+        w.write_line("one").unwrap();
+        write!(w, "two\nthr").unwrap();
+        assert_eq!(lines.len(), 0);
+        w.flush().unwrap();
+
+        let lines: Vec<String> = lines.to_vec().unwrap();
+        assert_eq!(lines, ["one\n", "two\n", "thr"]);
+    }
+}
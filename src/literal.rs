@@ -0,0 +1,160 @@
+#[cfg(feature = "magnus")]
+use std::sync::OnceLock;
+
+// Backing storage for one `red_lit!` call site. The string is created the
+// first time the site runs and registered with the GC for the rest of the
+// process, so every later call is a load and a pointer check.
+#[cfg(feature = "magnus")]
+pub struct LiteralCache {
+    value: OnceLock<rb_sys::VALUE>,
+}
+
+#[cfg(feature = "magnus")]
+impl LiteralCache {
+    pub const fn new() -> Self {
+        Self {
+            value: OnceLock::new(),
+        }
+    }
+
+    pub fn get(&self, lit: &'static str) -> magnus::RString {
+        use magnus::rb_sys::FromRawValue;
+
+        let raw = *self.value.get_or_init(|| register(lit));
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw) }).unwrap()
+    }
+}
+
+#[cfg(feature = "magnus")]
+impl Default for LiteralCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A fixed set of literals created together at init, e.g.
+//
+//     static KEYS: OnceLock<LiteralTable> = OnceLock::new();
+//     KEYS.get_or_init(|| redrs::register_literals(&["id", "name"]));
+//
+// and then looked up by index on hot paths, with no hashing or interning.
+// The table lives on the system heap so it can be kept in a static; it is
+// never freed, like the strings it points to.
+#[cfg(feature = "magnus")]
+pub struct LiteralTable {
+    literals: &'static [&'static str],
+    values: Box<[rb_sys::VALUE]>,
+}
+
+#[cfg(feature = "magnus")]
+pub fn register_literals(literals: &'static [&'static str]) -> LiteralTable {
+    LiteralTable {
+        literals,
+        values: literals.iter().map(|lit| register(lit)).collect(),
+    }
+}
+
+#[cfg(feature = "magnus")]
+impl LiteralTable {
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
+    // Panics if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> magnus::RString {
+        use magnus::rb_sys::FromRawValue;
+
+        let raw = self.values[idx];
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw) }).unwrap()
+    }
+
+    // The bytes to push when building a `RedString` rather than Ruby values.
+    pub fn as_str(&self, idx: usize) -> &'static str {
+        self.literals[idx]
+    }
+}
+
+// Interned strings are frozen and deduplicated, so this is the same object
+// Ruby uses for an equal frozen literal. Registering it keeps it alive, and
+// in place under compaction, for the rest of the process.
+#[cfg(feature = "magnus")]
+fn register(lit: &str) -> rb_sys::VALUE {
+    unsafe {
+        let raw = rb_sys::rb_enc_interned_str(
+            lit.as_ptr() as *const i8,
+            lit.len().try_into().unwrap(),
+            rb_sys::rb_utf8_encoding(),
+        );
+        crate::set_coderange(raw, lit.is_ascii());
+        rb_sys::rb_gc_register_mark_object(raw);
+        raw
+    }
+}
+
+// Returns the same frozen `RString` for `lit` on every call from a given
+// call site, creating it only once.
+#[cfg(feature = "magnus")]
+#[macro_export]
+macro_rules! red_lit {
+    ($lit:literal) => {{
+        static CACHE: $crate::LiteralCache = $crate::LiteralCache::new();
+        CACHE.get(concat!($lit))
+    }};
+}
+
+// Appends `lit` to a `RedString` or `RedStringBuilder`. Unlike `red_lit!`
+// there is nothing to cache: the bytes are copied straight from the binary.
+#[macro_export]
+macro_rules! push_lit {
+    ($string:expr, $lit:literal) => {
+        ($string).push_str(concat!($lit))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_red_lit() {
+        use magnus::{prelude::*, rb_sys::AsRawValue};
+
+        let ids: Vec<_> = (0..3).map(|_| red_lit!("id").as_raw()).collect();
+        assert!(ids.iter().all(|&id| id == ids[0]));
+
+        let lit = red_lit!("id");
+        assert!(lit.is_frozen());
+        assert_eq!(lit.to_string().unwrap(), "id");
+        assert_ne!(red_lit!("null").as_raw(), lit.as_raw());
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_literal_table() {
+        use magnus::{prelude::*, rb_sys::AsRawValue};
+
+        let table = crate::register_literals(&["id", "name"]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.as_str(1), "name");
+        assert_eq!(table.get(1).as_raw(), table.get(1).as_raw());
+        assert!(table.get(0).is_frozen());
+        assert_eq!(table.get(0).to_string().unwrap(), "id");
+        assert_eq!(table.get(0).as_raw(), red_lit!("id").as_raw());
+    }
+
+    #[ruby_test]
+    fn test_push_lit() {
+        let mut s = RedString::new();
+        push_lit!(s, "[");
+        push_lit!(&mut s, "null");
+        push_lit!(s, ']');
+        assert_eq!(s.as_str(), "[null]");
+    }
+}
@@ -0,0 +1,202 @@
+use std::io;
+
+use magnus::rb_sys::protect;
+
+use crate::{gvl, Pinned, RedString};
+
+// Writers made by `RubyMakeWriter` borrow its logger rather than pinning it
+// again, since they may be created on threads where the GC can't be touched.
+enum Logger<'a> {
+    Owned(Pinned<magnus::Value>),
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    Borrowed(&'a Pinned<magnus::Value>),
+}
+
+impl Logger<'_> {
+    fn as_raw(&self) -> rb_sys::VALUE {
+        match self {
+            Logger::Owned(logger) => logger.as_raw(),
+            Logger::Borrowed(logger) => logger.as_raw(),
+        }
+    }
+}
+
+// Hands every complete line written to it, without the newline, to
+// `logger.<method>(line)`, so `RubyLogWriter::new(logger, "info")` logs
+// through `Logger#info`. A trailing partial line waits for the rest of it,
+// and is sent as is on `flush` or drop.
+//
+// Writes take the GVL if needed. On threads Ruby didn't create they fail
+// with an error instead, since there is no way to reach the logger there.
+pub struct RubyLogWriter<'a> {
+    logger: Logger<'a>,
+    method: rb_sys::ID,
+    line: RedString,
+}
+
+impl RubyLogWriter<'static> {
+    // `logger` is pinned for as long as the writer lives, so it stays alive
+    // no matter where the writer ends up.
+    pub fn new(logger: magnus::Value, method: &str) -> Self {
+        Self::with_target(Logger::Owned(Pinned::new(logger)), intern(method))
+    }
+}
+
+impl<'a> RubyLogWriter<'a> {
+    fn with_target(logger: Logger<'a>, method: rb_sys::ID) -> Self {
+        Self {
+            logger,
+            method,
+            line: RedString::new(),
+        }
+    }
+
+    fn send_lines(&mut self, include_partial: bool) -> Result<(), magnus::Error> {
+        let end = if include_partial {
+            self.line.len()
+        } else {
+            match self.line.rfind_byte(b'\n') {
+                Some(idx) => idx + 1,
+                None => return Ok(()),
+            }
+        };
+
+        let mut sent = 0;
+        let mut result = Ok(());
+        for line in self.line[..end].split_terminator('\n') {
+            result = call(self.logger.as_raw(), self.method, line);
+            if result.is_err() {
+                break;
+            }
+            sent += line.len() + 1;
+        }
+        // Lines Ruby already got are gone even if a later one raised.
+        self.line.remove_prefix(sent.min(end));
+        result
+    }
+}
+
+impl io::Write for RubyLogWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = std::str::from_utf8(buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        gvl::try_with_gvl(|| {
+            self.line.push_str(s);
+            self.send_lines(false)
+        })
+        .ok_or_else(not_ruby_thread)?
+        .map_err(ruby_error)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        gvl::try_with_gvl(|| self.send_lines(true))
+            .ok_or_else(not_ruby_thread)?
+            .map_err(ruby_error)
+    }
+}
+
+impl Drop for RubyLogWriter<'_> {
+    fn drop(&mut self) {
+        gvl::try_with_gvl(|| {
+            let _ = self.send_lines(true);
+            drop(std::mem::replace(&mut self.line, RedString::new()));
+        });
+    }
+}
+
+// One `RubyLogWriter` per event, all writing to the same logger, for
+// `tracing_subscriber::fmt().with_writer(...)`. The logger stays pinned until
+// this is dropped, which must happen on a Ruby thread.
+#[cfg(feature = "tracing")]
+pub struct RubyMakeWriter {
+    logger: Pinned<magnus::Value>,
+    method: rb_sys::ID,
+}
+
+#[cfg(feature = "tracing")]
+impl RubyMakeWriter {
+    pub fn new(logger: magnus::Value, method: &str) -> Self {
+        Self {
+            logger: Pinned::new(logger),
+            method: intern(method),
+        }
+    }
+}
+
+// Subscribers are shared across threads. The logger is only read there, and
+// only called into with the GVL held.
+#[cfg(feature = "tracing")]
+unsafe impl Send for RubyMakeWriter {}
+#[cfg(feature = "tracing")]
+unsafe impl Sync for RubyMakeWriter {}
+
+#[cfg(feature = "tracing")]
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for RubyMakeWriter {
+    type Writer = RubyLogWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RubyLogWriter::with_target(Logger::Borrowed(&self.logger), self.method)
+    }
+}
+
+fn intern(method: &str) -> rb_sys::ID {
+    unsafe {
+        rb_sys::rb_intern2(
+            method.as_ptr() as *const i8,
+            method.len().try_into().unwrap(),
+        )
+    }
+}
+
+fn call(logger: rb_sys::VALUE, method: rb_sys::ID, line: &str) -> Result<(), magnus::Error> {
+    protect(|| unsafe {
+        let arg =
+            rb_sys::rb_utf8_str_new(line.as_ptr() as *const i8, line.len().try_into().unwrap());
+        crate::set_coderange(arg, line.is_ascii());
+        rb_sys::rb_funcallv(logger, method, 1, &arg)
+    })?;
+    Ok(())
+}
+
+fn not_ruby_thread() -> io::Error {
+    io::Error::other("Ruby logger written to from a thread not created by Ruby")
+}
+
+fn ruby_error(err: magnus::Error) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use magnus::{eval, prelude::*, RArray};
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RubyLogWriter;
+
+    #[ruby_test]
+    fn test_log_writer() {
+        let lines = RArray::new();
+        let mut w = RubyLogWriter::new(lines.as_value(), "push");
+        write!(w, "first\nsec").unwrap();
+        assert_eq!(lines.len(), 1);
+        writeln!(w, "ond").unwrap();
+        write!(w, "partial").unwrap();
+        w.flush().unwrap();
+        write!(w, "dropped").unwrap();
+        drop(w);
+
+        let lines: Vec<String> = lines.to_vec().unwrap();
+        assert_eq!(lines, ["first", "second", "partial", "dropped"]);
+    }
+
+    #[ruby_test]
+    fn test_log_writer_raises() {
+        let logger = eval("o = Object.new; def o.info(_) = raise 'boom'; o").unwrap();
+        let mut w = RubyLogWriter::new(logger, "info");
+        let err = writeln!(w, "line").unwrap_err();
+        assert!(err.to_string().contains("boom"));
+    }
+}
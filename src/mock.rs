@@ -0,0 +1,51 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use allocator_api2::alloc::AllocError;
+
+// The system allocator standing in for Ruby's under `mock-alloc`, so the
+// string and vec logic can be unit tested, fuzzed and run under Miri without
+// a Ruby VM. Everything that creates Ruby objects still needs a real one.
+
+pub(crate) fn allocate(layout: Layout) -> Result<*mut libc::c_void, AllocError> {
+    let ptr = unsafe { System.alloc(layout) };
+    if ptr.is_null() {
+        return Err(AllocError);
+    }
+    Ok(ptr as *mut libc::c_void)
+}
+
+pub(crate) unsafe fn deallocate(ptr: *mut libc::c_void, layout: Layout) {
+    System.dealloc(ptr as *mut u8, layout);
+}
+
+pub(crate) unsafe fn reallocate(
+    ptr: *mut libc::c_void,
+    old_layout: Layout,
+    new_layout: Layout,
+) -> Result<*mut libc::c_void, AllocError> {
+    let new_ptr = System.realloc(ptr as *mut u8, old_layout, new_layout.size());
+    if new_ptr.is_null() {
+        return Err(AllocError);
+    }
+    Ok(new_ptr as *mut libc::c_void)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RedBytes, RedString};
+
+    // Plain `#[test]`s: nothing here boots Ruby.
+    #[test]
+    fn test_without_vm() {
+        let mut s = RedString::with_capacity(1);
+        s.push_str("grows ");
+        s.push_repeat("past its capacity ", 8);
+        s.replace_in_place("past", "beyond");
+        assert!(s.starts_with("grows beyond"));
+
+        let mut b = RedBytes::new();
+        b.put_varint(300);
+        b.splice(..1, b"\x00\x00");
+        assert_eq!(b.as_slice(), b"\x00\x00\x02");
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{RedBytes, RedVec, RubyAllocator, Slot};
+
+struct Frame {
+    map: bool,
+    len: usize,
+    has_key: bool,
+    header: Slot,
+}
+
+// The MessagePack counterpart of `json::Encoder`, with the same calls and
+// the same panics on misuse. Maps and arrays are prefixed with their entry
+// count, which isn't known until they end, so each one starts with a slot
+// for the largest header that is swapped for the smallest one that fits on
+// `end_map`/`end_array`. That moves the container's body, so deeply nested
+// documents copy their innermost bytes once per level.
+pub struct Encoder {
+    buf: RedBytes,
+    stack: RedVec<Frame>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::from_buf(RedBytes::new())
+    }
+
+    pub fn from_buf(buf: RedBytes) -> Self {
+        Self {
+            buf,
+            stack: RedVec::new_in(RubyAllocator::new()),
+        }
+    }
+
+    pub fn begin_map(&mut self) {
+        self.begin(true);
+    }
+
+    pub fn end_map(&mut self) {
+        self.end(true, 0x80, 0xde);
+    }
+
+    pub fn begin_array(&mut self) {
+        self.begin(false);
+    }
+
+    pub fn end_array(&mut self) {
+        self.end(false, 0x90, 0xdc);
+    }
+
+    pub fn key(&mut self, key: &str) {
+        match self.stack.last_mut() {
+            Some(frame) if frame.map && !frame.has_key => frame.has_key = true,
+            _ => panic!("MessagePack key outside a map or in place of a value"),
+        }
+        self.write_str(key);
+    }
+
+    pub fn str(&mut self, s: &str) {
+        self.begin_value();
+        self.write_str(s);
+    }
+
+    pub fn bin(&mut self, bytes: &[u8]) {
+        self.begin_value();
+        match bytes.len() {
+            len if len <= u8::MAX as usize => {
+                self.buf.put_u8(0xc4);
+                self.buf.put_u8(len as u8);
+            }
+            len if len <= u16::MAX as usize => {
+                self.buf.put_u8(0xc5);
+                self.buf.put_u16_be(len as u16);
+            }
+            len => {
+                self.buf.put_u8(0xc6);
+                self.buf
+                    .put_u32_be(len.try_into().expect("MessagePack bin too long"));
+            }
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    // Uses the shortest encoding for the value, as the spec recommends.
+    pub fn int(&mut self, n: i64) {
+        if n >= 0 {
+            return self.uint(n as u64);
+        }
+        self.begin_value();
+        match n {
+            -32..=-1 => self.buf.put_i8(n as i8),
+            n if n >= i8::MIN as i64 => {
+                self.buf.put_u8(0xd0);
+                self.buf.put_i8(n as i8);
+            }
+            n if n >= i16::MIN as i64 => {
+                self.buf.put_u8(0xd1);
+                self.buf.put_i16_be(n as i16);
+            }
+            n if n >= i32::MIN as i64 => {
+                self.buf.put_u8(0xd2);
+                self.buf.put_i32_be(n as i32);
+            }
+            n => {
+                self.buf.put_u8(0xd3);
+                self.buf.put_i64_be(n);
+            }
+        }
+    }
+
+    pub fn uint(&mut self, n: u64) {
+        self.begin_value();
+        match n {
+            0..=0x7f => self.buf.put_u8(n as u8),
+            n if n <= u8::MAX as u64 => {
+                self.buf.put_u8(0xcc);
+                self.buf.put_u8(n as u8);
+            }
+            n if n <= u16::MAX as u64 => {
+                self.buf.put_u8(0xcd);
+                self.buf.put_u16_be(n as u16);
+            }
+            n if n <= u32::MAX as u64 => {
+                self.buf.put_u8(0xce);
+                self.buf.put_u32_be(n as u32);
+            }
+            n => {
+                self.buf.put_u8(0xcf);
+                self.buf.put_u64_be(n);
+            }
+        }
+    }
+
+    pub fn float(&mut self, f: f64) {
+        self.begin_value();
+        self.buf.put_u8(0xcb);
+        self.buf.put_f64_be(f);
+    }
+
+    pub fn bool(&mut self, b: bool) {
+        self.begin_value();
+        self.buf.put_u8(if b { 0xc3 } else { 0xc2 });
+    }
+
+    pub fn null(&mut self) {
+        self.begin_value();
+        self.buf.put_u8(0xc0);
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.buf.as_slice()
+    }
+
+    // Panics if a map or array is still open.
+    pub fn into_inner(self) -> RedBytes {
+        assert!(self.stack.is_empty(), "MessagePack map or array left open");
+        self.buf
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        self.into_inner().into_rstring()
+    }
+
+    fn write_str(&mut self, s: &str) {
+        match s.len() {
+            len if len < 32 => self.buf.put_u8(0xa0 | len as u8),
+            len if len <= u8::MAX as usize => {
+                self.buf.put_u8(0xd9);
+                self.buf.put_u8(len as u8);
+            }
+            len if len <= u16::MAX as usize => {
+                self.buf.put_u8(0xda);
+                self.buf.put_u16_be(len as u16);
+            }
+            len => {
+                self.buf.put_u8(0xdb);
+                self.buf
+                    .put_u32_be(len.try_into().expect("MessagePack str too long"));
+            }
+        }
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn begin(&mut self, map: bool) {
+        self.begin_value();
+        let header = self.buf.reserve_slot(5);
+        self.stack.push(Frame {
+            map,
+            len: 0,
+            has_key: false,
+            header,
+        });
+    }
+
+    fn begin_value(&mut self) {
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+        if frame.map {
+            assert!(frame.has_key, "MessagePack value in a map without a key");
+            frame.has_key = false;
+        }
+        frame.len += 1;
+    }
+
+    // `fix` and `tag16` are the fixmap/fixarray and map16/array16 tags; the
+    // 32-bit tag follows the 16-bit one.
+    fn end(&mut self, map: bool, fix: u8, tag16: u8) {
+        let frame = match self.stack.pop() {
+            Some(frame) if frame.map == map && !frame.has_key => frame,
+            _ => panic!(
+                "unbalanced MessagePack end_{}",
+                if map { "map" } else { "array" }
+            ),
+        };
+        let mut header = [0; 5];
+        let header = match frame.len {
+            len if len < 16 => {
+                header[0] = fix | len as u8;
+                &header[..1]
+            }
+            len if len <= u16::MAX as usize => {
+                header[0] = tag16;
+                header[1..3].copy_from_slice(&(len as u16).to_be_bytes());
+                &header[..3]
+            }
+            len => {
+                let len: u32 = len.try_into().expect("MessagePack container too long");
+                header[0] = tag16 + 1;
+                header[1..].copy_from_slice(&len.to_be_bytes());
+                &header[..]
+            }
+        };
+        let offset = frame.header.offset();
+        if header.len() == frame.header.len() {
+            self.buf.patch(frame.header, header);
+        } else {
+            self.buf.splice(offset..offset + frame.header.len(), header);
+        }
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::Encoder;
+
+    #[ruby_test]
+    fn test_encoder() {
+        let mut mp = Encoder::new();
+        mp.begin_map();
+        mp.key("id");
+        mp.int(-1);
+        mp.key("n");
+        mp.begin_array();
+        mp.uint(200);
+        mp.int(-200);
+        mp.bool(false);
+        mp.null();
+        mp.end_array();
+        mp.end_map();
+        assert_eq!(
+            mp.into_inner().as_slice(),
+            b"\x82\xa2id\xff\xa1n\x94\xcc\xc8\xd1\xff\x38\xc2\xc0"
+        );
+    }
+
+    #[ruby_test]
+    fn test_large_containers() {
+        let mut mp = Encoder::new();
+        mp.begin_array();
+        for _ in 0..20 {
+            mp.null();
+        }
+        mp.end_array();
+        let bytes = mp.into_inner();
+        assert_eq!(&bytes[..3], b"\xdc\x00\x14");
+        assert_eq!(bytes.len(), 3 + 20);
+
+        let long = "x".repeat(40);
+        let mut mp = Encoder::new();
+        mp.str(&long);
+        mp.bin(b"\x00");
+        let bytes = mp.into_inner();
+        assert_eq!(&bytes[..2], b"\xd9\x28");
+        assert_eq!(&bytes[42..], b"\xc4\x01\x00");
+    }
+
+    #[ruby_test]
+    #[should_panic]
+    fn test_unclosed() {
+        let mut mp = Encoder::new();
+        mp.begin_map();
+        mp.into_inner();
+    }
+}
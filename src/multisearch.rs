@@ -0,0 +1,90 @@
+use aho_corasick::{AhoCorasick, BuildError, Input, MatchKind, PatternID};
+
+use crate::RedString;
+
+impl RedString {
+    // Every non-overlapping match of any of `patterns` in one pass, as the
+    // byte offset where it starts and the index of the pattern. Where
+    // patterns overlap, the one listed first wins, like in a regex
+    // alternation. Fails only if the patterns are too big to compile.
+    pub fn find_any(
+        &self,
+        patterns: &[&str],
+    ) -> Result<impl Iterator<Item = (usize, PatternID)> + '_, BuildError> {
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(patterns)?;
+        Ok(FindAny {
+            automaton,
+            haystack: self.as_bytes(),
+            pos: 0,
+        })
+    }
+}
+
+// `AhoCorasick::find_iter` borrows the automaton, so the iterator can't
+// return it alongside; this owns it and resumes the search by hand.
+struct FindAny<'h> {
+    automaton: AhoCorasick,
+    haystack: &'h [u8],
+    pos: usize,
+}
+
+impl Iterator for FindAny<'_> {
+    type Item = (usize, PatternID);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let input = Input::new(self.haystack).range(self.pos..);
+        let found = self.automaton.find(input)?;
+        // Step past empty matches (from an empty pattern) so we don't find
+        // the same one forever, to the next char like Ruby's `scan("")`.
+        self.pos = if found.is_empty() {
+            let continuation = self.haystack[found.end()..]
+                .iter()
+                .skip(1)
+                .take_while(|&&b| (b & 0xc0) == 0x80)
+                .count();
+            found.end() + continuation + 1
+        } else {
+            found.end()
+        };
+        Some((found.start(), found.pattern()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_find_any() {
+        let s = RedString::from_str("<script>héllo & <b>bye</b>");
+        let found: Vec<_> = s
+            .find_any(&["<script>", "<", "&", "bye"])
+            .unwrap()
+            .map(|(offset, pattern)| (offset, pattern.as_usize()))
+            .collect();
+        assert_eq!(found, [(0, 0), (15, 2), (17, 1), (20, 3), (23, 1)]);
+    }
+
+    #[ruby_test]
+    fn test_find_any_empty() {
+        let s = RedString::from_str("ab");
+        assert_eq!(s.find_any(&[""]).unwrap().count(), 3);
+        assert_eq!(s.find_any(&[]).unwrap().count(), 0);
+
+        let s = RedString::from_str("aé€");
+        let offsets: Vec<usize> = s
+            .find_any(&[""])
+            .unwrap()
+            .map(|(offset, _)| offset)
+            .collect();
+        assert_eq!(offsets, [0, 1, 3, 6]);
+        assert!(offsets.iter().all(|&offset| s.is_char_boundary(offset)));
+    }
+}
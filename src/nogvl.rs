@@ -0,0 +1,166 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use allocator_api2::alloc::AllocError;
+
+use crate::{
+    gvl::{has_gvl, with_gvl},
+    RubyAllocator,
+};
+
+// Buffers allocated with the system allocator because the GVL was not held.
+// Anything not in here came from `ruby_xmalloc`.
+static SYSTEM_BLOCKS: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+
+// How many blocks are in `SYSTEM_BLOCKS`, so the usual case of none at all
+// can skip the lock. A block is counted before it is handed out and uncounted
+// after it is removed, so whoever owns one always sees a non-zero count.
+static LIVE_SYSTEM_BLOCKS: AtomicUsize = AtomicUsize::new(0);
+
+fn system_blocks() -> std::sync::MutexGuard<'static, BTreeSet<usize>> {
+    SYSTEM_BLOCKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn is_system(ptr: *mut libc::c_void) -> bool {
+    LIVE_SYSTEM_BLOCKS.load(Ordering::Acquire) > 0 && system_blocks().contains(&(ptr as usize))
+}
+
+fn add_system(ptr: *mut libc::c_void) {
+    LIVE_SYSTEM_BLOCKS.fetch_add(1, Ordering::Release);
+    system_blocks().insert(ptr as usize);
+}
+
+fn remove_system(ptr: *mut libc::c_void) -> bool {
+    if LIVE_SYSTEM_BLOCKS.load(Ordering::Acquire) == 0 {
+        return false;
+    }
+    let removed = system_blocks().remove(&(ptr as usize));
+    if removed {
+        LIVE_SYSTEM_BLOCKS.fetch_sub(1, Ordering::Release);
+    }
+    removed
+}
+
+pub(crate) fn allocate(
+    size: usize,
+    ruby: impl FnOnce() -> Result<*mut libc::c_void, AllocError>,
+) -> Result<*mut libc::c_void, AllocError> {
+    if has_gvl() {
+        return ruby();
+    }
+    let ptr = unsafe { libc::malloc(size) };
+    if ptr.is_null() {
+        return Err(AllocError);
+    }
+    add_system(ptr);
+    Ok(ptr)
+}
+
+pub(crate) unsafe fn deallocate(ptr: *mut libc::c_void) {
+    if remove_system(ptr) {
+        libc::free(ptr);
+    } else {
+        with_gvl(|| rb_sys::ruby_xfree(ptr));
+    }
+}
+
+// A system buffer that is resized while the GVL is held moves over to Ruby's
+// heap, so strings built without the GVL end up accounted for by Ruby's GC
+// like any other.
+pub(crate) unsafe fn reallocate(
+    ptr: *mut libc::c_void,
+    old_size: usize,
+    new_size: usize,
+    ruby: impl FnOnce() -> Result<*mut libc::c_void, AllocError>,
+) -> Result<*mut libc::c_void, AllocError> {
+    match (is_system(ptr), has_gvl()) {
+        (false, true) => ruby(),
+        (false, false) => with_gvl(ruby),
+        (true, false) => {
+            let new_ptr = libc::realloc(ptr, new_size);
+            if new_ptr.is_null() {
+                return Err(AllocError);
+            }
+            let mut blocks = system_blocks();
+            blocks.remove(&(ptr as usize));
+            blocks.insert(new_ptr as usize);
+            Ok(new_ptr)
+        }
+        (true, true) => {
+            let size = new_size.try_into().map_err(|_| AllocError)?;
+            let new_ptr = RubyAllocator::call(|| rb_sys::ruby_xmalloc(size))?;
+            std::ptr::copy_nonoverlapping(
+                ptr as *const u8,
+                new_ptr as *mut u8,
+                old_size.min(new_size),
+            );
+            remove_system(ptr);
+            libc::free(ptr);
+            Ok(new_ptr)
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "mock-alloc")))]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::{RedBytes, RedString};
+
+    fn without_gvl<F: FnOnce()>(f: F) {
+        unsafe extern "C" fn call<F: FnOnce()>(arg: *mut libc::c_void) -> *mut libc::c_void {
+            ((*(arg as *mut Option<F>)).take().unwrap())();
+            std::ptr::null_mut()
+        }
+
+        let mut f = Some(f);
+        unsafe {
+            rb_sys::rb_thread_call_without_gvl(
+                Some(call::<F>),
+                &mut f as *mut Option<F> as *mut libc::c_void,
+                None,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+
+    #[ruby_test]
+    fn test_build_without_gvl() {
+        let mut s = RedString::new();
+        without_gvl(|| {
+            s.push_str("hello");
+            s.push_str(&" world".repeat(10));
+        });
+        assert!(super::is_system(s.as_ptr() as *mut libc::c_void));
+
+        s.push_str(&"!".repeat(100));
+        assert!(!super::is_system(s.as_ptr() as *mut libc::c_void));
+        assert!(s.starts_with("hello world world"));
+        assert!(s.ends_with("!!!"));
+    }
+
+    #[ruby_test]
+    fn test_drop_without_gvl() {
+        let s = RedString::from_str("allocated with the GVL");
+        without_gvl(move || drop(s));
+
+        let mut b = None;
+        without_gvl(|| b = Some(RedBytes::from_slice(b"allocated without the GVL")));
+        drop(b);
+    }
+
+    #[ruby_test]
+    fn test_into_raw_parts_migrates() {
+        let mut b = None;
+        without_gvl(|| b = Some(RedBytes::from_slice(b"abc")));
+        let (ptr, len, capacity) = b.unwrap().into_raw_parts();
+        assert!(!super::is_system(ptr as *mut libc::c_void));
+        let b = unsafe { RedBytes::from_raw_parts(ptr, len, capacity) };
+        assert_eq!(b.as_slice(), b"abc");
+    }
+}
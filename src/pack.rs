@@ -0,0 +1,221 @@
+use std::num::TryFromIntError;
+
+use crate::RedBytes;
+
+macro_rules! put_int {
+    ($($name:ident => $ty:ty, $to_bytes:ident;)*) => {
+        $(
+            pub fn $name(&mut self, value: $ty) {
+                self.extend_from_slice(&value.$to_bytes());
+            }
+        )*
+    };
+}
+
+// Counted strings fail instead of truncating when `bytes` doesn't fit the
+// length prefix.
+macro_rules! put_counted {
+    ($($name:ident => $put:ident, $ty:ty;)*) => {
+        $(
+            pub fn $name(&mut self, bytes: &[u8]) -> Result<(), TryFromIntError> {
+                let len = <$ty>::try_from(bytes.len())?;
+                self.reserve(std::mem::size_of::<$ty>() + bytes.len());
+                self.$put(len);
+                self.extend_from_slice(bytes);
+                Ok(())
+            }
+        )*
+    };
+}
+
+// A placeholder left by `reserve_slot`, to be filled in with `patch` once
+// the bytes that follow it are known. Not `Copy`, so each slot is patched at
+// most once.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Slot {
+    offset: usize,
+    len: usize,
+}
+
+impl Slot {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl RedBytes {
+    put_int! {
+        put_u8 => u8, to_ne_bytes;
+        put_i8 => i8, to_ne_bytes;
+        put_u16_le => u16, to_le_bytes;
+        put_u16_be => u16, to_be_bytes;
+        put_i16_le => i16, to_le_bytes;
+        put_i16_be => i16, to_be_bytes;
+        put_u32_le => u32, to_le_bytes;
+        put_u32_be => u32, to_be_bytes;
+        put_i32_le => i32, to_le_bytes;
+        put_i32_be => i32, to_be_bytes;
+        put_u64_le => u64, to_le_bytes;
+        put_u64_be => u64, to_be_bytes;
+        put_i64_le => i64, to_le_bytes;
+        put_i64_be => i64, to_be_bytes;
+        put_f32_le => f32, to_le_bytes;
+        put_f32_be => f32, to_be_bytes;
+        put_f64_le => f64, to_le_bytes;
+        put_f64_be => f64, to_be_bytes;
+    }
+
+    put_counted! {
+        put_counted_u8 => put_u8, u8;
+        put_counted_u16_le => put_u16_le, u16;
+        put_counted_u16_be => put_u16_be, u16;
+        put_counted_u32_le => put_u32_le, u32;
+        put_counted_u32_be => put_u32_be, u32;
+    }
+
+    // LEB128, as used by protobuf and most other varint formats.
+    pub fn put_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.push(value as u8);
+    }
+
+    // Zigzag-encoded first, so small negative numbers stay short.
+    pub fn put_signed_varint(&mut self, value: i64) {
+        self.put_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    // Writes `len` zero bytes to be overwritten later, so a length prefix can
+    // go in front of a body that hasn't been encoded yet.
+    pub fn reserve_slot(&mut self, len: usize) -> Slot {
+        let offset = self.len();
+        self.reserve(len);
+        self.buf.resize(offset + len, 0);
+        Slot { offset, len }
+    }
+
+    // Bytes written after `slot`, i.e. the size of the body it prefixes.
+    pub fn written_since(&self, slot: &Slot) -> usize {
+        self.len() - (slot.offset + slot.len)
+    }
+
+    // Panics if `bytes` isn't exactly as long as the slot, or if the buffer
+    // was truncated into it.
+    pub fn patch(&mut self, slot: Slot, bytes: &[u8]) {
+        assert_eq!(
+            bytes.len(),
+            slot.len,
+            "patch of {} bytes into a slot of {}",
+            bytes.len(),
+            slot.len
+        );
+        self.buf[slot.offset..slot.offset + slot.len].copy_from_slice(bytes);
+    }
+
+    // Like `Array#pack("a<width>")`: `bytes` is cut or padded to exactly
+    // `width` bytes.
+    pub fn put_padded(&mut self, bytes: &[u8], width: usize, pad: u8) {
+        let bytes = &bytes[..bytes.len().min(width)];
+        self.reserve(width);
+        self.extend_from_slice(bytes);
+        self.buf.resize(self.len() + width - bytes.len(), pad);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedBytes;
+
+    #[ruby_test]
+    fn test_put_int() {
+        let mut b = RedBytes::new();
+        b.put_u8(0xff);
+        b.put_i8(-1);
+        b.put_u16_le(0x0102);
+        b.put_u16_be(0x0102);
+        b.put_i32_be(-2);
+        b.put_u64_le(1);
+        assert_eq!(
+            b.as_slice(),
+            &[0xff, 0xff, 2, 1, 1, 2, 0xff, 0xff, 0xff, 0xfe, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[ruby_test]
+    fn test_put_float() {
+        let mut b = RedBytes::new();
+        b.put_f32_be(1.0);
+        b.put_f64_le(-2.5);
+        assert_eq!(&b[..4], &1.0f32.to_be_bytes());
+        assert_eq!(&b[4..], &(-2.5f64).to_le_bytes());
+    }
+
+    #[ruby_test]
+    fn test_put_counted() {
+        let mut b = RedBytes::new();
+        b.put_counted_u8(b"abc").unwrap();
+        b.put_counted_u16_be(b"de").unwrap();
+        assert_eq!(b.as_slice(), b"\x03abc\x00\x02de");
+        assert!(b.put_counted_u8(&[0; 256]).is_err());
+        assert_eq!(b.len(), 8);
+    }
+
+    #[ruby_test]
+    fn test_put_varint() {
+        let mut b = RedBytes::new();
+        b.put_varint(1);
+        b.put_varint(300);
+        b.put_varint(u64::MAX);
+        assert_eq!(b.as_slice(), b"\x01\xac\x02\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01");
+
+        let mut b = RedBytes::new();
+        b.put_signed_varint(0);
+        b.put_signed_varint(-1);
+        b.put_signed_varint(1);
+        b.put_signed_varint(-64);
+        assert_eq!(b.as_slice(), &[0, 1, 2, 127]);
+    }
+
+    #[ruby_test]
+    fn test_patch() {
+        let mut b = RedBytes::new();
+        let outer = b.reserve_slot(4);
+        b.put_u8(b'<');
+        let inner = b.reserve_slot(2);
+        b.extend_from_slice(b"body");
+        let inner_len = b.written_since(&inner) as u16;
+        b.patch(inner, &inner_len.to_be_bytes());
+        b.put_u8(b'>');
+        let outer_len = b.written_since(&outer) as u32;
+        b.patch(outer, &outer_len.to_be_bytes());
+        assert_eq!(b.as_slice(), b"\0\0\0\x08<\0\x04body>");
+    }
+
+    #[ruby_test]
+    #[should_panic]
+    fn test_patch_wrong_len() {
+        let mut b = RedBytes::new();
+        let slot = b.reserve_slot(2);
+        b.patch(slot, b"abc");
+    }
+
+    #[ruby_test]
+    fn test_put_padded() {
+        let mut b = RedBytes::new();
+        b.put_padded(b"ab", 4, 0);
+        b.put_padded(b"abcdef", 3, b' ');
+        assert_eq!(b.as_slice(), b"ab\0\0abc");
+    }
+}
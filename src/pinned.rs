@@ -0,0 +1,114 @@
+use std::ops::Deref;
+
+use magnus::{prelude::*, rb_sys::AsRawValue};
+
+use crate::GvlSend;
+
+// Keeps a Ruby object alive, and in place under compaction, for as long as
+// the handle exists, wherever the handle is stored. A `magnus::Value` is only
+// safe on the stack, where the GC finds it by scanning; one kept in a struct
+// on the heap can be collected or moved. The object is registered with
+// `rb_gc_register_address`, which marks it without letting it move, and
+// unregistered on drop.
+//
+// Derefs to `T`, so a pinned string can be handed to anything taking
+// `&RString`, like `RedStringBuilder::push_rstring` or
+// `RedCow::from_rstring`, with the borrow as long as the handle lives.
+// `RedLineWriter` and `RubyLogWriter` keep their IO, block or logger in one.
+pub struct Pinned<T: ReprValue> {
+    // Boxed so the registered address stays put when the handle moves.
+    slot: Box<T>,
+}
+
+impl<T: ReprValue> Pinned<T> {
+    pub fn new(value: T) -> Self {
+        // Every magnus value type is a transparent wrapper around `VALUE`.
+        assert_eq!(
+            std::mem::size_of::<T>(),
+            std::mem::size_of::<rb_sys::VALUE>()
+        );
+        let slot = Box::new(value);
+        unsafe { rb_sys::rb_gc_register_address(&*slot as *const T as *mut rb_sys::VALUE) };
+        Self { slot }
+    }
+
+    pub fn get(&self) -> T {
+        *self.slot
+    }
+
+    pub fn as_raw(&self) -> rb_sys::VALUE {
+        self.slot.as_raw()
+    }
+}
+
+impl<T: ReprValue> Deref for Pinned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.slot
+    }
+}
+
+impl<T: ReprValue> Clone for Pinned<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
+}
+
+impl<T: ReprValue> Drop for Pinned<T> {
+    fn drop(&mut self) {
+        let slot = &*self.slot as *const T as *mut rb_sys::VALUE;
+        crate::gvl::with_gvl(|| unsafe { rb_sys::rb_gc_unregister_address(slot) });
+    }
+}
+
+// Registration is global and dropping reacquires the GVL, so a handle may
+// move to another Ruby thread inside a `GvlBound`.
+unsafe impl<T: ReprValue> GvlSend for Pinned<T> {}
+
+#[cfg(test)]
+mod tests {
+    use magnus::{prelude::*, RString};
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::Pinned;
+    use crate::{RedCow, RedStringBuilder};
+
+    #[ruby_test]
+    fn test_survives_compaction() {
+        let pinned: Vec<Pinned<RString>> = (0..100)
+            .map(|i| Pinned::new(RString::new(&format!("pinned string number {}", i))))
+            .collect();
+        let raw: Vec<_> = pinned.iter().map(|p| p.as_raw()).collect();
+
+        let _: magnus::Value =
+            magnus::eval("GC.start; GC.compact if GC.respond_to?(:compact)").unwrap();
+
+        for (i, p) in pinned.iter().enumerate() {
+            assert_eq!(p.as_raw(), raw[i]);
+            assert_eq!(
+                p.to_string().unwrap(),
+                format!("pinned string number {}", i)
+            );
+        }
+    }
+
+    #[ruby_test]
+    fn test_borrowed_views() {
+        let frozen = Pinned::new(RString::new("frozen"));
+        frozen.freeze();
+
+        let mut builder = RedStringBuilder::new();
+        builder.push_rstring(&frozen).unwrap();
+        builder.push_str("!");
+        assert_eq!(builder.into_rstring().to_string().unwrap(), "frozen!");
+
+        let cow = RedCow::from_rstring(&frozen).unwrap();
+        assert!(!cow.is_owned());
+        assert_eq!(cow.as_str(), "frozen");
+
+        let copy = frozen.clone();
+        drop(frozen);
+        assert_eq!(copy.to_string().unwrap(), "frozen");
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    cell::RefCell,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{RedString, RedVec, RubyAllocator};
+
+// Buckets hold buffers with capacity in `[2^k, 2^(k + 1))` for
+// `k` in `MIN_SHIFT..=MAX_SHIFT`. Anything smaller is cheaper to allocate
+// again than to track, and anything larger would pin too much memory.
+const MIN_SHIFT: u32 = 6;
+const MAX_SHIFT: u32 = 16;
+const BUCKETS: usize = (MAX_SHIFT - MIN_SHIFT + 1) as usize;
+const PER_BUCKET: usize = 16;
+
+const EMPTY_BUCKET: RedVec<RedString> = RedVec::new_in(RubyAllocator::new());
+
+thread_local! {
+    static POOL: RefCell<[RedVec<RedString>; BUCKETS]> = const {
+        RefCell::new([EMPTY_BUCKET; BUCKETS])
+    };
+}
+
+// Recycles `RedString` buffers on the current thread, for code that builds
+// many short-lived strings:
+//
+//     let mut s = RedStringPool::acquire();
+//     s.push_str("...");
+//     s.into_rstring() // the buffer goes back to the pool after the copy
+//
+// Cached buffers are freed when the thread exits, which may be after it
+// released the GVL; call `clear` first on threads that outlive the work
+// using the pool.
+pub struct RedStringPool;
+
+impl RedStringPool {
+    pub fn acquire() -> PooledString {
+        Self::acquire_with_capacity(0)
+    }
+
+    // Reuses a cached buffer of at least `capacity` bytes if there is one.
+    pub fn acquire_with_capacity(capacity: usize) -> PooledString {
+        let first = bucket_for_request(capacity);
+        let cached = POOL
+            .try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                pool.get_mut(first..)?
+                    .iter_mut()
+                    .find_map(|bucket| bucket.pop())
+            })
+            .ok()
+            .flatten();
+        PooledString {
+            s: ManuallyDrop::new(cached.unwrap_or_else(|| RedString::with_capacity(capacity))),
+        }
+    }
+
+    // Number of buffers cached on this thread.
+    pub fn cached() -> usize {
+        POOL.with(|pool| pool.borrow().iter().map(|bucket| bucket.len()).sum())
+    }
+
+    // Frees every buffer cached on this thread.
+    pub fn clear() {
+        let buckets = POOL.with(|pool| pool.replace([EMPTY_BUCKET; BUCKETS]));
+        drop(buckets);
+    }
+
+    fn release(mut s: RedString) {
+        let Some(idx) = bucket_for_capacity(s.capacity()) else {
+            return;
+        };
+        s.clear();
+        let _ = POOL.try_with(|pool| {
+            // Dropped outside the borrow, in case freeing ever calls back in.
+            let rejected = match pool.try_borrow_mut() {
+                Ok(mut pool) if pool[idx].len() < PER_BUCKET => {
+                    pool[idx].push(s);
+                    None
+                }
+                _ => Some(s),
+            };
+            drop(rejected);
+        });
+    }
+}
+
+fn bucket_for_request(capacity: usize) -> usize {
+    let shift = capacity.max(1).next_power_of_two().trailing_zeros();
+    shift.saturating_sub(MIN_SHIFT) as usize
+}
+
+fn bucket_for_capacity(capacity: usize) -> Option<usize> {
+    if capacity < 1 << MIN_SHIFT {
+        return None;
+    }
+    let shift = capacity.ilog2();
+    (shift <= MAX_SHIFT).then(|| (shift - MIN_SHIFT) as usize)
+}
+
+// A `RedString` that goes back to its thread's pool when dropped.
+pub struct PooledString {
+    s: ManuallyDrop<RedString>,
+}
+
+impl PooledString {
+    // Keeps the buffer out of the pool.
+    pub fn into_inner(mut self) -> RedString {
+        let s = unsafe { ManuallyDrop::take(&mut self.s) };
+        std::mem::forget(self);
+        s
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        self.clone_to_rstring()
+    }
+}
+
+impl Deref for PooledString {
+    type Target = RedString;
+
+    fn deref(&self) -> &Self::Target {
+        &self.s
+    }
+}
+
+impl DerefMut for PooledString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.s
+    }
+}
+
+impl std::fmt::Write for PooledString {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl Drop for PooledString {
+    fn drop(&mut self) {
+        RedStringPool::release(unsafe { ManuallyDrop::take(&mut self.s) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RedStringPool;
+
+    #[ruby_test]
+    fn test_reuse() {
+        RedStringPool::clear();
+
+        let mut s = RedStringPool::acquire_with_capacity(128);
+        s.push_str("hello");
+        let ptr = s.as_ptr();
+        drop(s);
+        assert_eq!(RedStringPool::cached(), 1);
+
+        let s = RedStringPool::acquire_with_capacity(80);
+        assert_eq!(s.as_ptr(), ptr);
+        assert!(s.is_empty());
+        assert_eq!(RedStringPool::cached(), 0);
+
+        // Too big for the cached buffer's bucket, so it is left alone.
+        drop(s);
+        let big = RedStringPool::acquire_with_capacity(1000);
+        assert_ne!(big.as_ptr(), ptr);
+        assert_eq!(RedStringPool::cached(), 1);
+
+        let kept = big.into_inner();
+        assert!(kept.capacity() >= 1000);
+        assert_eq!(RedStringPool::cached(), 1);
+
+        RedStringPool::clear();
+        assert_eq!(RedStringPool::cached(), 0);
+    }
+
+    #[ruby_test]
+    fn test_small_buffers_are_not_cached() {
+        RedStringPool::clear();
+        let mut s = RedStringPool::acquire();
+        s.push('x');
+        drop(s);
+        assert_eq!(RedStringPool::cached(), 0);
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_into_rstring_recycles() {
+        RedStringPool::clear();
+        let mut s = RedStringPool::acquire_with_capacity(256);
+        s.push_str("pooled");
+        let rstring = s.into_rstring();
+        assert_eq!(rstring.to_string().unwrap(), "pooled");
+        assert_eq!(RedStringPool::cached(), 1);
+        RedStringPool::clear();
+    }
+}
@@ -0,0 +1,80 @@
+use std::{cell::Cell, os::raw::c_int};
+
+thread_local! {
+    static FALLIBLE: Cell<bool> = const { Cell::new(false) };
+}
+
+// Marks allocations made by `f` as ones whose failure the caller can report,
+// i.e. the `try_*` methods. Everything else, like `push` or `reserve`, has
+// nowhere to put an error: `Vec` would abort the process through
+// `handle_alloc_error`, so those keep raising `NoMemoryError` like they do
+// without the `protected` feature.
+pub(crate) fn fallible<T>(f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FALLIBLE.with(|flag| flag.set(self.0));
+        }
+    }
+
+    let _restore = Restore(FALLIBLE.with(|flag| flag.replace(true)));
+    f()
+}
+
+pub(crate) fn is_fallible() -> bool {
+    FALLIBLE.with(Cell::get)
+}
+
+// Runs an allocation under `rb_protect` so a `NoMemoryError` comes back as
+// `None` instead of longjmp'ing over Rust frames. That exception is cleared,
+// because the caller reports the failure through a Rust error instead.
+// Anything else, like an `Interrupt`, a `Thread#kill` or an exception from a
+// finalizer run by the GC, is re-raised once we're back out of `rb_protect`,
+// where nothing but the allocator's own frames is left to skip; the
+// container is still in its pre-growth state, as when Ruby raises directly.
+pub(crate) fn protect<F>(f: F) -> Option<*mut libc::c_void>
+where
+    F: FnOnce() -> *mut libc::c_void,
+{
+    struct Call<F> {
+        f: Option<F>,
+        result: *mut libc::c_void,
+    }
+
+    unsafe extern "C" fn call<F>(arg: rb_sys::VALUE) -> rb_sys::VALUE
+    where
+        F: FnOnce() -> *mut libc::c_void,
+    {
+        let call = &mut *(arg as *mut Call<F>);
+        call.result = (call.f.take().unwrap())();
+        rb_sys::Qnil as rb_sys::VALUE
+    }
+
+    let mut args = Call {
+        f: Some(f),
+        result: std::ptr::null_mut(),
+    };
+    let mut state: c_int = 0;
+    unsafe {
+        rb_sys::rb_protect(
+            Some(call::<F>),
+            &mut args as *mut Call<F> as rb_sys::VALUE,
+            &mut state,
+        );
+    }
+
+    if state == 0 {
+        return Some(args.result);
+    }
+    unsafe {
+        if state == rb_sys::ruby_tag_type::RUBY_TAG_RAISE as c_int
+            && rb_sys::rb_obj_is_kind_of(rb_sys::rb_errinfo(), rb_sys::rb_eNoMemError)
+                == rb_sys::Qtrue as rb_sys::VALUE
+        {
+            rb_sys::rb_set_errinfo(rb_sys::Qnil as rb_sys::VALUE);
+            return None;
+        }
+        rb_sys::rb_jump_tag(state)
+    }
+}
@@ -0,0 +1,320 @@
+use std::ops::{Deref, DerefMut};
+
+#[cfg(feature = "magnus")]
+use magnus::rb_sys::FromRawValue;
+
+use crate::{RedVec, RubyAllocator};
+
+pub struct RedBytes {
+    pub(crate) buf: RedVec<u8>,
+}
+
+impl RedBytes {
+    pub const fn new() -> Self {
+        Self {
+            buf: RedVec::new_in(RubyAllocator::new()),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: RedVec::with_capacity_in(capacity, RubyAllocator::new()),
+        }
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut result = Self::with_capacity(bytes.len());
+        result.extend_from_slice(bytes);
+        result
+    }
+
+    /// Takes ownership of a buffer allocated by Ruby's allocator.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must have been returned by `ruby_xmalloc`, `ruby_xmalloc2`,
+    ///   `ruby_xcalloc` or `ruby_xrealloc` (or [`RedBytes::into_raw_parts`])
+    ///   and not been freed, with at least `capacity` bytes allocated. It may
+    ///   be null only if `capacity` is 0.
+    /// - `len` must not exceed `capacity`, and the first `len` bytes must be
+    ///   initialized.
+    /// - Nothing else may use or free the buffer afterwards; it is released
+    ///   with `ruby_xfree` when the `RedBytes` is dropped.
+    pub unsafe fn from_raw_parts(ptr: *mut u8, len: usize, capacity: usize) -> Self {
+        let ptr = if capacity == 0 {
+            std::ptr::NonNull::dangling().as_ptr()
+        } else {
+            ptr
+        };
+        Self {
+            buf: RedVec::from_raw_parts_in(ptr, len, capacity, RubyAllocator::new()),
+        }
+    }
+
+    /// Releases the buffer as `(ptr, len, capacity)`. The caller becomes
+    /// responsible for freeing it with `ruby_xfree`, or for handing it back
+    /// through [`RedBytes::from_raw_parts`]. `ptr` is null when `capacity` is
+    /// 0.
+    pub fn into_raw_parts(self) -> (*mut u8, usize, usize) {
+        #[cfg(feature = "nogvl")]
+        let (ptr, len, capacity) = self.into_ruby_heap().buf.into_raw_parts();
+        #[cfg(not(feature = "nogvl"))]
+        let (ptr, len, capacity) = self.buf.into_raw_parts();
+        if capacity == 0 {
+            (std::ptr::null_mut(), len, capacity)
+        } else {
+            (ptr, len, capacity)
+        }
+    }
+
+    // Callers of `into_raw_parts` free the buffer with `ruby_xfree`, so it
+    // has to be moved off the system heap first.
+    #[cfg(feature = "nogvl")]
+    fn into_ruby_heap(self) -> Self {
+        if self.buf.capacity() == 0
+            || !crate::nogvl::is_system(self.buf.as_ptr() as *mut libc::c_void)
+        {
+            return self;
+        }
+        assert!(
+            crate::gvl::has_gvl(),
+            "into_raw_parts requires the GVL for buffers built without it"
+        );
+        let mut buf = RedVec::with_capacity_in(self.buf.capacity(), RubyAllocator::new());
+        buf.extend_from_slice(&self.buf);
+        Self { buf }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        crate::growth::reserve(&mut self.buf, 1);
+        self.buf.push(byte);
+    }
+
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        crate::growth::reserve(&mut self.buf, bytes.len());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn extend_from_slices(&mut self, slices: &[&[u8]]) {
+        self.extend_from_iter(slices.iter().copied());
+    }
+
+    // One reserve for the total, then plain copies.
+    pub(crate) fn extend_from_iter<'b, I>(&mut self, slices: I)
+    where
+        I: Iterator<Item = &'b [u8]> + Clone,
+    {
+        let total = slices.clone().map(<[u8]>::len).sum();
+        crate::growth::reserve(&mut self.buf, total);
+        let len = self.buf.len();
+        unsafe {
+            let mut dst = self.buf.as_mut_ptr().add(len);
+            for slice in slices {
+                std::ptr::copy_nonoverlapping(slice.as_ptr(), dst, slice.len());
+                dst = dst.add(slice.len());
+            }
+            self.buf.set_len(len + total);
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        crate::growth::reserve(&mut self.buf, additional);
+    }
+
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.buf.reserve_exact(additional);
+    }
+
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), allocator_api2::alloc::AllocError> {
+        crate::growth::try_reserve(&mut self.buf, additional)
+    }
+
+    pub fn try_extend_from_slice(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<(), allocator_api2::alloc::AllocError> {
+        self.try_reserve(bytes.len())?;
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.buf
+    }
+
+    pub fn reader(&self) -> std::io::Cursor<&[u8]> {
+        std::io::Cursor::new(self.as_slice())
+    }
+
+    pub fn into_reader(self) -> std::io::Cursor<Self> {
+        std::io::Cursor::new(self)
+    }
+
+    pub fn into_raw_rstring(self) -> rb_sys::VALUE {
+        unsafe {
+            let raw_value = rb_sys::rb_str_new(
+                self.buf.as_ptr() as *const i8,
+                self.buf.len().try_into().unwrap(),
+            );
+            crate::set_coderange(raw_value, self.buf.is_ascii());
+            raw_value
+        }
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> magnus::RString {
+        let raw_value = self.into_raw_rstring();
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+}
+
+impl Default for RedBytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for RedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for RedBytes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl AsRef<[u8]> for RedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl std::io::Write for RedBytes {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+        let len = self.len();
+        self.extend_from_iter(bufs.iter().map(|buf| &**buf));
+        Ok(self.len() - len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RedBytes;
+
+    #[ruby_test]
+    fn test_push() {
+        let mut b = RedBytes::new();
+        b.push(0);
+        b.extend_from_slice(&[1, 2, 255]);
+        assert_eq!(b.len(), 4);
+        assert_eq!(b.as_slice(), &[0, 1, 2, 255]);
+    }
+
+    #[ruby_test]
+    fn test_extend_from_slices() {
+        let mut b = RedBytes::from_slice(b"<");
+        b.extend_from_slices(&[b"head", b"", b"body", b"tail"]);
+        assert_eq!(b.as_slice(), b"<headbodytail");
+        assert_eq!(b.capacity(), b.len());
+    }
+
+    #[ruby_test]
+    fn test_write_vectored() {
+        use std::io::{IoSlice, Write};
+
+        let mut b = RedBytes::new();
+        let written = b
+            .write_vectored(&[IoSlice::new(b"ab"), IoSlice::new(b"cd")])
+            .unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(b.as_slice(), b"abcd");
+    }
+
+    #[ruby_test]
+    fn test_write() {
+        let mut b = RedBytes::new();
+        std::io::Write::write_all(&mut b, b"abc").unwrap();
+        assert_eq!(b.as_slice(), b"abc");
+    }
+
+    #[ruby_test]
+    fn test_try_extend_from_slice() {
+        let mut b = RedBytes::new();
+        b.try_extend_from_slice(b"abc").unwrap();
+        assert_eq!(b.as_slice(), b"abc");
+        assert!(b.try_reserve(usize::MAX).is_err());
+        assert_eq!(b.as_slice(), b"abc");
+    }
+
+    #[ruby_test]
+    fn test_reader() {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let mut reader = RedBytes::from_slice(&[1, 2, 3, 4]).into_reader();
+        reader.seek(SeekFrom::End(-2)).unwrap();
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [3, 4]);
+    }
+
+    #[ruby_test]
+    fn test_raw_parts() {
+        let b = RedBytes::from_slice(b"abc");
+        let (ptr, len, capacity) = b.into_raw_parts();
+        assert_eq!((len, capacity), (3, 3));
+        let b = unsafe { RedBytes::from_raw_parts(ptr, len, capacity) };
+        assert_eq!(b.as_slice(), b"abc");
+
+        let (ptr, len, capacity) = RedBytes::new().into_raw_parts();
+        assert!(ptr.is_null());
+        let b = unsafe { RedBytes::from_raw_parts(ptr, len, capacity) };
+        assert!(b.is_empty());
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_into_rstring() {
+        let b = RedBytes::from_slice(&[0, 159, 255]);
+        let rstring = b.into_rstring();
+        assert_eq!(unsafe { rstring.as_slice() }, &[0, 159, 255]);
+    }
+}
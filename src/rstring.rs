@@ -0,0 +1,607 @@
+use magnus::{
+    rb_sys::{protect, AsRawValue, FromRawId, FromRawValue},
+    value::ReprValue,
+};
+
+use crate::RedString;
+
+impl RedString {
+    pub fn concat_rstrings<I>(strings: I) -> Result<Self, magnus::Error>
+    where
+        I: IntoIterator<Item = magnus::RString>,
+        I::IntoIter: Clone,
+    {
+        Self::join_rstrings(strings, "")
+    }
+
+    pub fn join_rstrings<I>(strings: I, separator: &str) -> Result<Self, magnus::Error>
+    where
+        I: IntoIterator<Item = magnus::RString>,
+        I::IntoIter: Clone,
+    {
+        let strings = strings.into_iter();
+        let (count, len) = strings
+            .clone()
+            .fold((0usize, 0), |(count, len), s| (count + 1, len + s.len()));
+
+        let mut result = Self::with_capacity(len + separator.len() * count.saturating_sub(1));
+        for (i, s) in strings.enumerate() {
+            if i > 0 {
+                result.push_str(separator);
+            }
+            result.push_str(unsafe { s.as_str()? });
+        }
+
+        Ok(result)
+    }
+
+    pub fn into_rstring(self) -> magnus::RString {
+        let raw_value = self.into_raw_rstring();
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+
+    // The Ruby string is created with this string's capacity, so Ruby code
+    // that keeps appending to it doesn't have to reallocate straight away.
+    pub fn into_rstring_buf(self) -> magnus::RString {
+        let raw_value = unsafe {
+            let raw_value = rb_sys::rb_str_buf_new(self.capacity().try_into().unwrap());
+            rb_sys::rb_str_cat(
+                raw_value,
+                self.buf.as_ptr() as *const i8,
+                self.buf.len().try_into().unwrap(),
+            );
+            rb_sys::rb_enc_associate_index(raw_value, rb_sys::rb_utf8_encindex());
+            crate::set_coderange(raw_value, self.buf.is_ascii());
+            raw_value
+        };
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+
+    // Marked safe so Rails doesn't escape the string again, which is only
+    // right if everything untrusted went through `push_html_escaped`. Without
+    // ActiveSupport loaded this is a plain UTF-8 string.
+    pub fn into_html_safe_rstring(self) -> Result<magnus::RString, magnus::Error> {
+        let raw_value = self.into_raw_rstring();
+        let raw_value = match safe_buffer_class()? {
+            Some(class) => {
+                protect(|| unsafe { rb_sys::rb_class_new_instance(1, &raw_value, class) })?
+            }
+            None => raw_value,
+        };
+        Ok(magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap())
+    }
+
+    // Always a full copy; `SnapshotBuilder` shares one buffer between
+    // snapshots taken with no writes in between.
+    pub fn clone_to_rstring(&self) -> magnus::RString {
+        let raw_value = self.clone_to_raw_rstring();
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+
+    // `None` if the range is out of bounds or doesn't fall on char boundaries.
+    pub fn slice_to_rstring<R>(&self, range: R) -> Option<magnus::RString>
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let slice = self.as_str().get(range)?;
+        let raw_value = unsafe {
+            let raw_value = rb_sys::rb_utf8_str_new(
+                slice.as_ptr() as *const i8,
+                slice.len().try_into().unwrap(),
+            );
+            crate::set_coderange(raw_value, slice.is_ascii());
+            raw_value
+        };
+        Some(magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap())
+    }
+
+    // Snapshots handed to callbacks are usually meant to be read, not edited;
+    // freezing them also lets Ruby share their buffer when they get dup'ed
+    // or used as hash keys instead of copying again.
+    pub fn clone_to_frozen_rstring(&self) -> magnus::RString {
+        let raw_value = unsafe { rb_sys::rb_obj_freeze(self.clone_to_raw_rstring()) };
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+
+    // Both hand off what has been written so far and keep the allocation, so
+    // a long-running producer can flush in chunks with bounded memory.
+    pub fn drain_to_rstring(&mut self) -> magnus::RString {
+        let rstring = self.clone_to_rstring();
+        self.clear();
+        rstring
+    }
+
+    pub fn drain_append_to(&mut self, rstring: magnus::RString) -> Result<(), magnus::Error> {
+        self.append_to_rstring(rstring)?;
+        self.clear();
+        Ok(())
+    }
+
+    // Ruby negotiates the encoding like `String#<<` does: appending to an
+    // ASCII-only string in another ASCII-compatible encoding is fine, and a
+    // non-ASCII one raises `Encoding::CompatibilityError`. Frozen strings
+    // raise `FrozenError`. Either way `rstring` is left unchanged.
+    pub fn append_to_rstring(&self, rstring: magnus::RString) -> Result<(), magnus::Error> {
+        let raw = rstring.as_raw();
+        let ptr = self.buf.as_ptr() as *const i8;
+        let len = self.buf.len().try_into().unwrap();
+        protect(|| unsafe {
+            rb_sys::rb_enc_str_buf_cat(raw, ptr, len, rb_sys::rb_utf8_encoding())
+        })?;
+        Ok(())
+    }
+
+    // Encodings other than UTF-8, US-ASCII and binary are left to Ruby's own
+    // `rb_str_inspect`.
+    pub fn push_inspect_rstring(
+        &mut self,
+        rstring: magnus::RString,
+    ) -> Result<(), magnus::Error> {
+        let raw = rstring.as_raw();
+        let bytes = unsafe { rstring.as_slice() };
+        let encindex = unsafe { rb_sys::rb_enc_get_index(raw) };
+        if encindex == unsafe { rb_sys::rb_utf8_encindex() } {
+            self.push_inspect_bytes(bytes, true);
+        } else if encindex == unsafe { rb_sys::rb_usascii_encindex() }
+            || encindex == unsafe { rb_sys::rb_ascii8bit_encindex() }
+        {
+            self.push_inspect_bytes(bytes, false);
+        } else {
+            let raw_value = protect(|| unsafe { rb_sys::rb_str_inspect(raw) })?;
+            let inspected =
+                magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap();
+            self.push_str(unsafe { inspected.as_str()? });
+        }
+        Ok(())
+    }
+
+    pub fn casecmp_rstring(
+        &self,
+        other: magnus::RString,
+    ) -> Result<std::cmp::Ordering, magnus::Error> {
+        Ok(self.casecmp(unsafe { other.as_str()? }))
+    }
+
+    #[cfg(feature = "unicode")]
+    pub fn casecmp_eq_rstring(&self, other: magnus::RString) -> Result<bool, magnus::Error> {
+        Ok(self.casecmp_eq(unsafe { other.as_str()? }))
+    }
+
+    // Formats with Ruby's own `format`, so every directive, flag and error
+    // (`ArgumentError` for a bad directive or missing argument) is Ruby's.
+    pub fn push_sprintf(&mut self, fmt: &str, args: &[magnus::Value]) -> Result<(), magnus::Error> {
+        let mut raw_args = crate::RedVec::with_capacity_in(args.len(), crate::RubyAllocator::new());
+        raw_args.extend(args.iter().map(|arg| arg.as_raw()));
+        let raw_string = protect(|| unsafe {
+            let raw_fmt =
+                rb_sys::rb_utf8_str_new(fmt.as_ptr() as *const i8, fmt.len().try_into().unwrap());
+            rb_sys::rb_str_format(raw_args.len().try_into().unwrap(), raw_args.as_ptr(), raw_fmt)
+        })?;
+        self.push_raw_rstring(raw_string)
+    }
+
+    pub fn push_value(&mut self, val: magnus::Value) -> Result<(), magnus::Error> {
+        let raw = val.as_raw();
+        let raw_string = protect(|| unsafe { rb_sys::rb_obj_as_string(raw) })?;
+        self.push_raw_rstring(raw_string)
+    }
+
+    pub fn push_value_inspect(&mut self, val: magnus::Value) -> Result<(), magnus::Error> {
+        let raw = val.as_raw();
+        let raw_string = protect(|| unsafe { rb_sys::rb_inspect(raw) })?;
+        self.push_raw_rstring(raw_string)
+    }
+
+    // Strings in other encodings are converted with `String#encode`
+    // semantics, so bytes that have no UTF-8 equivalent are an error rather
+    // than being copied in as invalid UTF-8.
+    fn push_raw_rstring(&mut self, raw: rb_sys::VALUE) -> Result<(), magnus::Error> {
+        let mut rstring =
+            magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw) }).unwrap();
+        if !rstring.is_utf8_compatible_encoding() {
+            let raw_value = protect(|| unsafe {
+                let utf8 = rb_sys::rb_enc_from_encoding(rb_sys::rb_utf8_encoding());
+                rb_sys::rb_str_encode(raw, utf8, 0, rb_sys::Qnil as rb_sys::VALUE)
+            })?;
+            rstring =
+                magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap();
+        }
+        self.push_str(unsafe { rstring.as_str()? });
+        Ok(())
+    }
+
+    pub fn into_id(self) -> magnus::value::Id {
+        unsafe {
+            let id = rb_sys::rb_intern3(
+                self.buf.as_ptr() as *const i8,
+                self.buf.len().try_into().unwrap(),
+                rb_sys::rb_utf8_encoding(),
+            );
+            magnus::value::Id::from_raw(id)
+        }
+    }
+
+    pub fn into_symbol(self) -> magnus::Symbol {
+        self.into_id().into()
+    }
+
+    pub fn split_to_rarray(&self, pattern: &str) -> magnus::RArray {
+        split_to_rarray(self.as_str(), pattern)
+    }
+
+    pub fn eq_rstring(&self, other: magnus::RString) -> bool {
+        let bytes = unsafe { other.as_slice() };
+        bytes.len() == self.len() && bytes == self.as_bytes() && self.comparable_with(other)
+    }
+
+    pub fn cmp_rstring(&self, other: magnus::RString) -> std::cmp::Ordering {
+        let bytes = unsafe { other.as_slice() };
+        match self.as_bytes().cmp(bytes) {
+            // same bytes in incompatible encodings, ordered like Ruby does
+            // by encoding index
+            std::cmp::Ordering::Equal if !self.comparable_with(other) => unsafe {
+                rb_sys::rb_utf8_encindex().cmp(&rb_sys::rb_enc_get_index(other.as_raw()))
+            },
+            ordering => ordering,
+        }
+    }
+
+    // Only meaningful once the bytes are known to be equal: either the other
+    // string is UTF-8 too, or it is plain ASCII in an ASCII-compatible
+    // encoding, which is what Ruby's `rb_str_comparable` boils down to here.
+    fn comparable_with(&self, other: magnus::RString) -> bool {
+        unsafe {
+            let raw = other.as_raw();
+            rb_sys::rb_enc_get_index(raw) == rb_sys::rb_utf8_encindex()
+                || rb_sys::rb_enc_str_asciionly_p(raw) != 0
+        }
+    }
+}
+
+impl magnus::IntoValue for RedString {
+    fn into_value_with(self, _handle: &magnus::Ruby) -> magnus::Value {
+        self.into_rstring().as_value()
+    }
+}
+
+unsafe impl magnus::IntoValueFromNative for RedString {}
+
+impl magnus::TryConvert for RedString {
+    fn try_convert(val: magnus::Value) -> Result<Self, magnus::Error> {
+        let rstring = magnus::RString::try_convert(val)?;
+        Ok(Self::from_str(unsafe { rstring.as_str()? }))
+    }
+}
+
+unsafe impl magnus::try_convert::TryConvertOwned for RedString {}
+
+// Looked up on every call, since ActiveSupport may be loaded after us.
+fn safe_buffer_class() -> Result<Option<rb_sys::VALUE>, magnus::Error> {
+    let mut namespace = unsafe { rb_sys::rb_cObject };
+    for name in [c"ActiveSupport", c"SafeBuffer"] {
+        let id = unsafe { rb_sys::rb_intern(name.as_ptr()) };
+        let is_module = unsafe { rb_sys::rb_obj_is_kind_of(namespace, rb_sys::rb_cModule) }
+            != rb_sys::Qfalse as rb_sys::VALUE;
+        if !is_module || unsafe { rb_sys::rb_const_defined(namespace, id) } == 0 {
+            return Ok(None);
+        }
+        namespace = protect(|| unsafe { rb_sys::rb_const_get(namespace, id) })?;
+    }
+    Ok(Some(namespace))
+}
+
+// Splits like Ruby's `String#split` with a string pattern and no limit:
+// trailing empty fields are dropped, an empty pattern splits into chars, and
+// `" "` splits on runs of ASCII whitespace, ignoring leading whitespace.
+// Fields are pushed as they are found, so the text is only scanned once.
+pub(crate) fn split_to_rarray(s: &str, pattern: &str) -> magnus::RArray {
+    let raw_array = unsafe { rb_sys::rb_ary_new() };
+    // Empty fields are held back until a non-empty one follows them.
+    let mut empty = 0;
+    let mut push = |field: &str| {
+        if field.is_empty() {
+            empty += 1;
+            return;
+        }
+        for _ in 0..empty {
+            push_piece(raw_array, "");
+        }
+        empty = 0;
+        push_piece(raw_array, field);
+    };
+
+    if pattern.is_empty() {
+        for (idx, c) in s.char_indices() {
+            push(&s[idx..idx + c.len_utf8()]);
+        }
+    } else if pattern == " " {
+        // Runs of whitespace are one separator, so there are no empty fields.
+        s.split(|c: char| matches!(c, ' ' | '\t'..='\r'))
+            .filter(|field| !field.is_empty())
+            .for_each(|field| push_piece(raw_array, field));
+    } else {
+        s.split(pattern).for_each(push);
+    }
+
+    magnus::RArray::from_value(unsafe { magnus::Value::from_raw(raw_array) }).unwrap()
+}
+
+fn push_piece(raw_array: rb_sys::VALUE, piece: &str) {
+    unsafe {
+        let raw_piece =
+            rb_sys::rb_utf8_str_new(piece.as_ptr() as *const i8, piece.len().try_into().unwrap());
+        crate::set_coderange(raw_piece, piece.is_ascii());
+        rb_sys::rb_ary_push(raw_array, raw_piece);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::prelude::*;
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_drain_to_rstring() {
+        let mut s = RedString::with_capacity(64);
+        s.push_str("first");
+        let first = s.drain_to_rstring();
+        s.push_str("second");
+        let second = s.drain_to_rstring();
+        assert_eq!(first.to_string().unwrap(), "first");
+        assert_eq!(second.to_string().unwrap(), "second");
+        assert!(s.is_empty());
+        assert_eq!(s.capacity(), 64);
+    }
+
+    #[ruby_test]
+    fn test_append_to_rstring() {
+        let s = RedString::from_str("ümlaut");
+        let out = magnus::RString::new("an ");
+        s.append_to_rstring(out).unwrap();
+        assert_eq!(out.to_string().unwrap(), "an ümlaut");
+        assert_eq!(s.as_str(), "ümlaut");
+
+        let ascii: magnus::RString = magnus::eval("'ok '.force_encoding('US-ASCII')").unwrap();
+        s.append_to_rstring(ascii).unwrap();
+        let latin1: magnus::RString =
+            magnus::eval(r#""\xE9".force_encoding('ISO-8859-1')"#).unwrap();
+        assert!(s.append_to_rstring(latin1).is_err());
+        assert_eq!(unsafe { latin1.as_slice() }, b"\xE9");
+    }
+
+    #[ruby_test]
+    fn test_drain_append_to() {
+        let out = magnus::RString::new("a");
+        let mut s = RedString::with_capacity(64);
+        s.push_str("b");
+        s.drain_append_to(out).unwrap();
+        s.push_str("c");
+        s.drain_append_to(out).unwrap();
+        assert_eq!(out.to_string().unwrap(), "abc");
+        assert_eq!(s.capacity(), 64);
+
+        out.freeze();
+        s.push_str("d");
+        assert!(s.drain_append_to(out).is_err());
+        assert_eq!(s.as_str(), "d");
+    }
+
+    #[ruby_test]
+    fn test_push_inspect_rstring() {
+        let mut s = RedString::from_str("got ");
+        s.push_inspect_rstring(magnus::RString::new("a\"b")).unwrap();
+        s.push(' ');
+        s.push_inspect_rstring(magnus::RString::from_slice(b"\xff")).unwrap();
+        assert_eq!(s.as_str(), r#"got "a\"b" "\xFF""#);
+    }
+
+    #[ruby_test]
+    fn test_slice_to_rstring() {
+        let s = RedString::from_str("héllo world");
+        assert_eq!(s.slice_to_rstring(..6).unwrap().to_string().unwrap(), "héllo");
+        assert_eq!(s.slice_to_rstring(7..).unwrap().to_string().unwrap(), "world");
+        assert_eq!(s.slice_to_rstring(3..=3).unwrap().to_string().unwrap(), "l");
+        assert!(s.slice_to_rstring(2..4).is_none());
+        assert!(s.slice_to_rstring(..100).is_none());
+    }
+
+    #[ruby_test]
+    fn test_into_rstring_buf() {
+        use magnus::rb_sys::AsRawValue;
+
+        let mut s = RedString::with_capacity(1024);
+        s.push_str("héllo");
+        let rstring = s.into_rstring_buf();
+        assert_eq!(rstring.to_string().unwrap(), "héllo");
+        assert!(unsafe { rb_sys::rb_str_capacity(rstring.as_raw()) } >= 1024);
+    }
+
+    #[ruby_test]
+    fn test_coderange() {
+        use magnus::encoding::Coderange;
+
+        let ascii = RedString::from_str("plain").into_rstring();
+        assert_eq!(ascii.enc_coderange(), Coderange::SevenBit);
+        let utf8 = RedString::from_str("héllo").into_rstring();
+        assert_eq!(utf8.enc_coderange(), Coderange::Valid);
+        let binary = crate::RedBytes::from_slice(b"\xff").into_rstring();
+        assert_eq!(binary.enc_coderange(), Coderange::Valid);
+
+        let mut builder = crate::RedStringBuilder::new();
+        builder.push_str("abc");
+        builder.push_str("日本");
+        assert_eq!(builder.into_rstring().enc_coderange(), Coderange::Valid);
+    }
+
+    #[ruby_test]
+    fn test_casecmp_rstring() {
+        let s = RedString::from_str("Accept");
+        let header = magnus::RString::new("ACCEPT");
+        assert_eq!(s.casecmp_rstring(header).unwrap(), std::cmp::Ordering::Equal);
+        #[cfg(feature = "unicode")]
+        assert!(RedString::from_str("ǅ").casecmp_eq_rstring(magnus::RString::new("ǆ")).unwrap());
+    }
+
+    #[ruby_test]
+    fn test_push_sprintf() {
+        let name: magnus::Value = magnus::eval("'pi'").unwrap();
+        let pi: magnus::Value = magnus::eval("3.14159").unwrap();
+        let hex: magnus::Value = magnus::eval("255").unwrap();
+        let mut s = RedString::from_str("> ");
+        s.push_sprintf("%-4s|%08.3f|%x", &[name, pi, hex]).unwrap();
+        assert_eq!(s.as_str(), "> pi  |0003.142|ff");
+
+        let named: magnus::Value = magnus::eval("{ name: 'hash' }").unwrap();
+        s.push_sprintf(" %<name>s!", &[named]).unwrap();
+        assert_eq!(s.as_str(), "> pi  |0003.142|ff hash!");
+
+        assert!(s.push_sprintf("%d %d", &[hex]).is_err());
+        assert_eq!(s.as_str(), "> pi  |0003.142|ff hash!");
+    }
+
+    #[ruby_test]
+    fn test_push_value() {
+        let mut s = RedString::from_str("got ");
+        s.push_value(magnus::eval("42").unwrap()).unwrap();
+        s.push_str(" and ");
+        s.push_value_inspect(magnus::eval("'héllo'").unwrap()).unwrap();
+        s.push_str(" and ");
+        let latin1 = magnus::eval(r#""caf\xE9".force_encoding('ISO-8859-1')"#).unwrap();
+        s.push_value(latin1).unwrap();
+        assert_eq!(s.as_str(), "got 42 and \"héllo\" and café");
+
+        let binary = magnus::eval(r#""\xFF".b"#).unwrap();
+        assert!(s.push_value(binary).is_err());
+    }
+
+    #[ruby_test]
+    fn test_into_html_safe_rstring() {
+        let mut s = RedString::from_str("<b>");
+        s.push_html_escaped("a < b");
+        s.push_str("</b>");
+        let plain = s.into_html_safe_rstring().unwrap();
+        assert_eq!(plain.to_string().unwrap(), "<b>a &lt; b</b>");
+
+        let _: magnus::Value = magnus::eval(
+            r#"
+            module ActiveSupport
+              class SafeBuffer < String
+                def initialize(str = "")
+                  @html_safe = true
+                  super
+                end
+
+                def html_safe? = @html_safe
+              end
+            end
+            "#,
+        )
+        .unwrap();
+        let safe = RedString::from_str("<i>ok</i>").into_html_safe_rstring().unwrap();
+        let html_safe: bool = safe.funcall("html_safe?", ()).unwrap();
+        // Removed before asserting, so no other test sees it even on failure.
+        let _: magnus::Value = magnus::eval("Object.send(:remove_const, :ActiveSupport)").unwrap();
+        assert!(html_safe);
+        assert_eq!(safe.to_string().unwrap(), "<i>ok</i>");
+    }
+
+    #[ruby_test]
+    fn test_clone_to_rstring() {
+        let mut s = RedString::from_str("10%");
+        let first = s.clone_to_rstring();
+        s.push_str(" 20%");
+        let second = s.clone_to_frozen_rstring();
+        s.push_str(" 30%");
+        assert_eq!(first.to_string().unwrap(), "10%");
+        assert_eq!(second.to_string().unwrap(), "10% 20%");
+        assert!(second.is_frozen());
+        assert_eq!(s.as_str(), "10% 20% 30%");
+    }
+
+    #[ruby_test]
+    fn test_into_symbol() {
+        let s = RedString::from_str("foo_bar");
+        assert_eq!(s.into_symbol().name().unwrap(), "foo_bar");
+    }
+
+    #[ruby_test]
+    fn test_into_id() {
+        let id = RedString::from_str("foo").into_id();
+        assert_eq!(id, RedString::from_str("foo").into_id());
+        assert_eq!(magnus::Symbol::from(id).name().unwrap(), "foo");
+    }
+
+    #[ruby_test]
+    fn test_split_to_rarray() {
+        let s = RedString::from_str("a,b,,c");
+        let array = s.split_to_rarray(",");
+        assert_eq!(array.len(), 4);
+        let pieces: Vec<String> = array.to_vec().unwrap();
+        assert_eq!(pieces, ["a", "b", "", "c"]);
+
+        // Same results as `String#split` in Ruby.
+        for (s, pattern) in [
+            (",a,,b,,", ","),
+            ("héllo", ""),
+            ("  a \t b\n", " "),
+            ("", ","),
+            (",,", ","),
+        ] {
+            let ruby: Vec<String> = magnus::RString::new(s)
+                .funcall("split", (pattern,))
+                .unwrap();
+            let ours: Vec<String> = RedString::from_str(s)
+                .split_to_rarray(pattern)
+                .to_vec()
+                .unwrap();
+            assert_eq!(ours, ruby, "{:?}.split({:?})", s, pattern);
+        }
+    }
+
+    #[ruby_test]
+    fn test_eq_rstring() {
+        let s = RedString::from_str("abc");
+        assert!(s.eq_rstring(magnus::RString::new("abc")));
+        assert!(!s.eq_rstring(magnus::RString::new("abd")));
+        assert!(!s.eq_rstring(magnus::RString::new("ab")));
+    }
+
+    #[ruby_test]
+    fn test_cmp_rstring() {
+        let s = RedString::from_str("b");
+        assert_eq!(s.cmp_rstring(magnus::RString::new("a")), std::cmp::Ordering::Greater);
+        assert_eq!(s.cmp_rstring(magnus::RString::new("b")), std::cmp::Ordering::Equal);
+        assert_eq!(s.cmp_rstring(magnus::RString::new("bb")), std::cmp::Ordering::Less);
+    }
+
+    #[ruby_test]
+    fn test_join_rstrings() {
+        let strings = [magnus::RString::new("a"), magnus::RString::new("bc")];
+        let s = RedString::join_rstrings(strings, ", ").unwrap();
+        assert_eq!(s.as_str(), "a, bc");
+        assert_eq!(s.capacity(), 5);
+
+        let s = RedString::concat_rstrings(strings).unwrap();
+        assert_eq!(s.as_str(), "abc");
+    }
+
+    #[ruby_test]
+    fn test_into_value() {
+        let value = magnus::IntoValue::into_value(RedString::from_str("abc"));
+        let rstring = magnus::RString::from_value(value).unwrap();
+        assert_eq!(rstring.to_string().unwrap(), "abc");
+    }
+
+    #[ruby_test]
+    fn test_try_convert() {
+        let value = magnus::IntoValue::into_value(magnus::RString::new("abc"));
+        let s: RedString = magnus::TryConvert::try_convert(value).unwrap();
+        assert_eq!(s.as_str(), "abc");
+
+        let value = magnus::IntoValue::into_value(magnus::Symbol::new("abc"));
+        assert!(<RedString as magnus::TryConvert>::try_convert(value).is_err());
+    }
+}
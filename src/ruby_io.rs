@@ -0,0 +1,157 @@
+use magnus::{
+    rb_sys::{protect, AsRawValue, FromRawValue},
+    TryConvert,
+};
+
+use crate::{RedBytes, RedString};
+
+impl RedString {
+    // Plain `File`s and `IO`s in binary mode, with a UTF-8 or binary external
+    // encoding, get the bytes straight from this buffer. Anything else (text
+    // mode, which may convert newlines, a transcoding IO, subclasses that
+    // override `write`, StringIO and other duck types) goes through `write`
+    // with a temporary string, so Ruby applies the IO's conversions as usual.
+    pub fn write_to_io(&self, io: magnus::Value) -> Result<usize, magnus::Error> {
+        write_str_to_io(io, self.as_str())
+    }
+}
+
+impl RedBytes {
+    pub fn write_to_io(&self, io: magnus::Value) -> Result<usize, magnus::Error> {
+        if writes_raw(io)? {
+            return write_bytes(io, self.as_slice());
+        }
+        let raw_value = unsafe {
+            rb_sys::rb_str_new(self.as_ptr() as *const i8, self.len().try_into().unwrap())
+        };
+        write_rstring(io, raw_value)
+    }
+}
+
+pub(crate) fn write_str_to_io(io: magnus::Value, s: &str) -> Result<usize, magnus::Error> {
+    if writes_raw(io)? && writes_utf8_unchanged(io)? {
+        return write_bytes(io, s.as_bytes());
+    }
+    let raw_value = unsafe {
+        let raw_value =
+            rb_sys::rb_utf8_str_new(s.as_ptr() as *const i8, s.len().try_into().unwrap());
+        crate::set_coderange(raw_value, s.is_ascii());
+        raw_value
+    };
+    write_rstring(io, raw_value)
+}
+
+// Only binary mode rules out newline conversion, and only the exact classes
+// rule out a `write` override that `rb_io_bufwrite` would skip.
+fn writes_raw(io: magnus::Value) -> Result<bool, magnus::Error> {
+    let raw = io.as_raw();
+    let class = unsafe { rb_sys::rb_obj_class(raw) };
+    if class != unsafe { rb_sys::rb_cFile } && class != unsafe { rb_sys::rb_cIO } {
+        return Ok(false);
+    }
+    let binmode =
+        protect(|| unsafe { rb_sys::rb_funcall(raw, rb_sys::rb_intern(c"binmode?".as_ptr()), 0) })?;
+    Ok(binmode == rb_sys::Qtrue as rb_sys::VALUE)
+}
+
+fn writes_utf8_unchanged(io: magnus::Value) -> Result<bool, magnus::Error> {
+    let raw = io.as_raw();
+    let encoding = protect(|| unsafe {
+        rb_sys::rb_funcall(raw, rb_sys::rb_intern(c"external_encoding".as_ptr()), 0)
+    })?;
+    if encoding == rb_sys::Qnil as rb_sys::VALUE {
+        return Ok(true);
+    }
+    let encindex = unsafe { rb_sys::rb_to_encoding_index(encoding) };
+    Ok(unsafe {
+        encindex == rb_sys::rb_utf8_encindex() || encindex == rb_sys::rb_ascii8bit_encindex()
+    })
+}
+
+fn write_bytes(io: magnus::Value, bytes: &[u8]) -> Result<usize, magnus::Error> {
+    let raw = io.as_raw();
+    let mut written = 0;
+    protect(|| unsafe {
+        written = rb_sys::rb_io_bufwrite(
+            raw,
+            bytes.as_ptr() as *const libc::c_void,
+            bytes.len().try_into().unwrap(),
+        );
+        if written < 0 {
+            rb_sys::rb_sys_fail(std::ptr::null());
+        }
+        rb_sys::Qnil as rb_sys::VALUE
+    })?;
+    Ok(written as usize)
+}
+
+fn write_rstring(io: magnus::Value, rstring: rb_sys::VALUE) -> Result<usize, magnus::Error> {
+    let raw = io.as_raw();
+    let written = protect(|| unsafe { rb_sys::rb_io_write(raw, rstring) })?;
+    usize::try_convert(unsafe { magnus::Value::from_raw(written) })
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::{eval, prelude::*, RString};
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::{RedBytes, RedString};
+
+    #[ruby_test]
+    fn test_write_to_file() {
+        let path = std::env::temp_dir().join(format!("redrs-write-to-io-{}", std::process::id()));
+        let io: magnus::Value =
+            eval(&format!("File.open({:?}, 'wb')", path.to_str().unwrap())).unwrap();
+
+        let s = RedString::from_str("héllo ");
+        assert_eq!(s.write_to_io(io).unwrap(), 7);
+        assert_eq!(RedBytes::from_slice(b"world").write_to_io(io).unwrap(), 5);
+        let _: magnus::Value = io.funcall("close", ()).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "héllo world");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[ruby_test]
+    fn test_write_to_io_converts_newlines() {
+        let path = std::env::temp_dir().join(format!("redrs-write-crlf-{}", std::process::id()));
+        let io: magnus::Value = eval(&format!(
+            "File.open({:?}, 'w', newline: :crlf)",
+            path.to_str().unwrap()
+        ))
+        .unwrap();
+
+        RedString::from_str("a\n").write_to_io(io).unwrap();
+        RedBytes::from_slice(b"b\n").write_to_io(io).unwrap();
+        let _: magnus::Value = io.funcall("close", ()).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"a\r\nb\r\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[ruby_test]
+    fn test_write_to_io_subclass() {
+        let path =
+            std::env::temp_dir().join(format!("redrs-write-subclass-{}", std::process::id()));
+        let io: magnus::Value = eval(&format!(
+            "Class.new(File) {{ def write(s) = super(s.upcase) }}.open({:?}, 'wb')",
+            path.to_str().unwrap()
+        ))
+        .unwrap();
+
+        RedString::from_str("abc").write_to_io(io).unwrap();
+        let _: magnus::Value = io.funcall("close", ()).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ABC");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[ruby_test]
+    fn test_write_to_stringio() {
+        let io: magnus::Value = eval("require 'stringio'; StringIO.new").unwrap();
+        RedString::from_str("abc").write_to_io(io).unwrap();
+        let out: RString = io.funcall("string", ()).unwrap();
+        assert_eq!(out.to_string().unwrap(), "abc");
+    }
+}
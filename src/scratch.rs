@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+
+use crate::RedString;
+
+// A reusable buffer for a `thread_local!`. It starts empty and only calls
+// into Ruby's allocator on the first push; after that each use starts from
+// a cleared string that keeps the capacity earlier uses grew it to:
+//
+//     thread_local! {
+//         static SCRATCH: ScratchBuffer = const { ScratchBuffer::new() };
+//     }
+//
+//     SCRATCH.with(|scratch| scratch.with(|s| render(s, rows)))
+//
+// Not `Sync`, so it can't be a plain `static`. The buffer is freed when the
+// thread exits, which may be after it released the GVL; call `release`
+// first on threads that outlive the work using it.
+pub struct ScratchBuffer {
+    buf: RefCell<RedString>,
+}
+
+impl ScratchBuffer {
+    pub const fn new() -> Self {
+        Self {
+            buf: RefCell::new(RedString::new()),
+        }
+    }
+
+    // Panics if called again from inside `f`.
+    pub fn with<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut RedString) -> T,
+    {
+        let mut buf = self.buf.borrow_mut();
+        buf.clear();
+        f(&mut buf)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.borrow().capacity()
+    }
+
+    // Frees the buffer; the next use allocates again.
+    pub fn release(&self) {
+        *self.buf.borrow_mut() = RedString::new();
+    }
+}
+
+impl Default for ScratchBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::ScratchBuffer;
+    use crate::{RedBytes, RedString, RedVec, RubyAllocator};
+
+    const EMPTY: RedString = RedString::new();
+    const EMPTY_BYTES: RedBytes = RedBytes::new();
+    const EMPTY_VEC: RedVec<u32> = RedVec::new_in(RubyAllocator::new());
+
+    thread_local! {
+        static SCRATCH: ScratchBuffer = const { ScratchBuffer::new() };
+    }
+
+    #[ruby_test]
+    fn test_const_new() {
+        assert_eq!(EMPTY.capacity(), 0);
+        assert!(EMPTY_BYTES.is_empty());
+        assert_eq!(EMPTY_VEC.capacity(), 0);
+    }
+
+    #[ruby_test]
+    fn test_scratch_reuse() {
+        SCRATCH.with(|scratch| {
+            assert_eq!(scratch.capacity(), 0);
+            let out = scratch.with(|s| {
+                s.push_str("hello");
+                s.as_str().to_owned()
+            });
+            assert_eq!(out, "hello");
+
+            let capacity = scratch.capacity();
+            assert!(capacity >= 5);
+            scratch.with(|s| {
+                assert!(s.is_empty());
+                s.push_str("hi");
+            });
+            assert_eq!(scratch.capacity(), capacity);
+
+            scratch.release();
+            assert_eq!(scratch.capacity(), 0);
+        });
+    }
+}
@@ -0,0 +1,74 @@
+use memchr::memmem;
+
+use crate::{RedBytes, RedString};
+
+// Byte offsets, found with memchr's SIMD routines. On `RedString`, a match
+// of an ASCII byte or of a valid UTF-8 needle always starts on a char
+// boundary.
+impl RedString {
+    pub fn find_byte(&self, byte: u8) -> Option<usize> {
+        memchr::memchr(byte, self.as_bytes())
+    }
+
+    pub fn rfind_byte(&self, byte: u8) -> Option<usize> {
+        memchr::memrchr(byte, self.as_bytes())
+    }
+
+    pub fn find_bytes(&self, needle: &[u8]) -> Option<usize> {
+        memmem::find(self.as_bytes(), needle)
+    }
+
+    pub fn rfind_bytes(&self, needle: &[u8]) -> Option<usize> {
+        memmem::rfind(self.as_bytes(), needle)
+    }
+}
+
+impl RedBytes {
+    pub fn find_byte(&self, byte: u8) -> Option<usize> {
+        memchr::memchr(byte, self.as_slice())
+    }
+
+    pub fn rfind_byte(&self, byte: u8) -> Option<usize> {
+        memchr::memrchr(byte, self.as_slice())
+    }
+
+    pub fn find_bytes(&self, needle: &[u8]) -> Option<usize> {
+        memmem::find(self.as_slice(), needle)
+    }
+
+    pub fn rfind_bytes(&self, needle: &[u8]) -> Option<usize> {
+        memmem::rfind(self.as_slice(), needle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::{RedBytes, RedString};
+
+    #[ruby_test]
+    fn test_find_byte() {
+        let s = RedString::from_str("a,b,c");
+        assert_eq!(s.find_byte(b','), Some(1));
+        assert_eq!(s.rfind_byte(b','), Some(3));
+        assert_eq!(s.find_byte(b';'), None);
+
+        let b = RedBytes::from_slice(b"\x00\xff\x00");
+        assert_eq!(b.find_byte(0), Some(0));
+        assert_eq!(b.rfind_byte(0), Some(2));
+    }
+
+    #[ruby_test]
+    fn test_find_bytes() {
+        let s = RedString::from_str("héllo\r\n\r\nbody\r\n");
+        assert_eq!(s.find_bytes(b"\r\n\r\n"), Some(6));
+        assert_eq!(s.rfind_bytes(b"\r\n"), Some(14));
+        assert_eq!(s.find_bytes("llo".as_bytes()), Some(3));
+
+        let b = RedBytes::from_slice(b"--frame--frame");
+        assert_eq!(b.find_bytes(b"frame"), Some(2));
+        assert_eq!(b.rfind_bytes(b"frame"), Some(9));
+        assert_eq!(b.find_bytes(b"none"), None);
+    }
+}
@@ -0,0 +1,220 @@
+#[cfg(feature = "magnus")]
+use magnus::rb_sys::FromRawValue;
+
+use crate::{hash::hash_bytes, RedString, RedVec, RubyAllocator};
+
+const EMPTY: usize = usize::MAX;
+
+struct Entry {
+    hash: u64,
+    string: RedString,
+}
+
+// A set of strings kept entirely in Ruby-allocated memory, so its size shows
+// up in Ruby's malloc accounting and counts towards GC pressure. Strings are
+// hashed like Ruby's `String#hash` and iterate in insertion order:
+//
+//     let mut seen = RedStringSet::new();
+//     for line in input.lines() {
+//         seen.insert(line);
+//     }
+//     seen.into_rarray_frozen()
+pub struct RedStringSet {
+    entries: RedVec<Entry>,
+    // Open addressing with linear probing. Each slot holds an index into
+    // `entries`, or `EMPTY`; there are no deletions, so no tombstones.
+    slots: RedVec<usize>,
+}
+
+impl RedStringSet {
+    pub fn new() -> Self {
+        Self {
+            entries: RedVec::new_in(RubyAllocator::new()),
+            slots: RedVec::new_in(RubyAllocator::new()),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut set = Self::new();
+        if capacity > 0 {
+            set.entries.reserve_exact(capacity);
+            set.rehash(slots_for(capacity));
+        }
+        set
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, s: &str) -> bool {
+        self.get(s).is_some()
+    }
+
+    pub fn get(&self, s: &str) -> Option<&RedString> {
+        let index = self.find(hash_bytes(s.as_bytes()), s).ok()?;
+        Some(&self.entries[index].string)
+    }
+
+    // `true` if `s` wasn't in the set yet.
+    pub fn insert(&mut self, s: &str) -> bool {
+        let len = self.len();
+        self.insert_get(s);
+        self.len() > len
+    }
+
+    // Returns the stored copy of `s`, adding one first if there isn't one.
+    pub fn insert_get(&mut self, s: &str) -> &RedString {
+        let hash = hash_bytes(s.as_bytes());
+        let index = match self.find(hash, s) {
+            Ok(index) => index,
+            Err(mut slot) => {
+                if (self.len() + 1) * 4 > self.slots.len() * 3 {
+                    self.rehash(slots_for(self.len() + 1).max(self.slots.len() * 2));
+                    slot = self.find(hash, s).unwrap_err();
+                }
+                self.slots[slot] = self.entries.len();
+                self.entries.push(Entry {
+                    hash,
+                    string: RedString::from_str(s),
+                });
+                self.entries.len() - 1
+            }
+        };
+        &self.entries[index].string
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RedString> {
+        self.entries.iter().map(|entry| &entry.string)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.slots.fill(EMPTY);
+    }
+
+    // Frozen strings can be shared by `dup` and used as `Hash` keys without
+    // another copy, which is what dedup results usually end up as.
+    #[cfg(feature = "magnus")]
+    pub fn into_rarray_frozen(self) -> magnus::RArray {
+        let raw_array = unsafe { rb_sys::rb_ary_new_capa(self.len().try_into().unwrap()) };
+        for entry in self.entries {
+            unsafe {
+                let raw_string = rb_sys::rb_obj_freeze(entry.string.into_raw_rstring());
+                rb_sys::rb_ary_push(raw_array, raw_string);
+            }
+        }
+        magnus::RArray::from_value(unsafe { magnus::Value::from_raw(raw_array) }).unwrap()
+    }
+
+    // `Ok` with the entry index if `s` is present, otherwise `Err` with the
+    // empty slot it would go in. An empty table has no slots, so that `Err`
+    // is only a placeholder; inserting always grows the table first.
+    fn find(&self, hash: u64, s: &str) -> Result<usize, usize> {
+        if self.slots.is_empty() {
+            return Err(0);
+        }
+        let mask = self.slots.len() - 1;
+        let mut slot = hash as usize & mask;
+        loop {
+            match self.slots[slot] {
+                EMPTY => return Err(slot),
+                index => {
+                    let entry = &self.entries[index];
+                    if entry.hash == hash && entry.string.as_str() == s {
+                        return Ok(index);
+                    }
+                }
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    fn rehash(&mut self, count: usize) {
+        self.slots.clear();
+        self.slots.resize(count, EMPTY);
+        let mask = count - 1;
+        for (index, entry) in self.entries.iter().enumerate() {
+            let mut slot = entry.hash as usize & mask;
+            while self.slots[slot] != EMPTY {
+                slot = (slot + 1) & mask;
+            }
+            self.slots[slot] = index;
+        }
+    }
+}
+
+impl Default for RedStringSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Extend<&'a str> for RedStringSet {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            self.insert(s);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a str> for RedStringSet {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+// Keeps the load factor at or under 3/4.
+fn slots_for(len: usize) -> usize {
+    (len * 4 / 3 + 1).next_power_of_two().max(8)
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::RedStringSet;
+
+    #[ruby_test]
+    fn test_dedup() {
+        let mut set = RedStringSet::new();
+        assert!(set.insert("a"));
+        assert!(!set.insert("a"));
+        let first = set.insert_get("b").as_ptr();
+        assert_eq!(set.insert_get("b").as_ptr(), first);
+        assert!(set.contains("a"));
+        assert!(!set.contains("c"));
+        assert_eq!(set.len(), 2);
+
+        let lines: Vec<String> = (0..1000).map(|i| format!("línea {}", i % 300)).collect();
+        set.extend(lines.iter().map(String::as_str));
+        assert_eq!(set.len(), 302);
+        let order: Vec<&str> = set.iter().skip(2).take(2).map(|s| s.as_str()).collect();
+        assert_eq!(order, ["línea 0", "línea 1"]);
+
+        set.clear();
+        assert!(set.is_empty());
+        assert!(!set.contains("a"));
+        assert!(set.insert("a"));
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_into_rarray_frozen() {
+        use magnus::{prelude::*, RString};
+
+        let set: RedStringSet = ["x", "y", "x", "é"].into_iter().collect();
+        let array = set.into_rarray_frozen();
+        assert_eq!(array.len(), 3);
+        let strings: Vec<RString> = array.to_vec().unwrap();
+        assert!(strings.iter().all(|s| s.is_frozen()));
+        let strings: Vec<String> = strings.iter().map(|s| s.to_string().unwrap()).collect();
+        assert_eq!(strings, ["x", "y", "é"]);
+    }
+}
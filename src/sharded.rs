@@ -0,0 +1,172 @@
+#[cfg(feature = "magnus")]
+use magnus::rb_sys::FromRawValue;
+
+use crate::RedString;
+
+// A piece of output written on a worker thread. Shards live on the system
+// heap, so unlike everything else in this crate they can be filled without
+// the GVL, from threads Ruby doesn't know about (e.g. a rayon pool).
+#[derive(Clone, Debug, Default)]
+pub struct Shard {
+    buf: String,
+}
+
+impl Shard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: String::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.buf.push(c);
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.buf.push_str(s);
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+}
+
+impl std::fmt::Write for Shard {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+// Shards are filled in parallel, then gathered in order with one allocation
+// of the exact total size once back on a thread holding the GVL:
+//
+//     let mut builder = ShardedBuilder::new(rows.len());
+//     builder.shards_mut().par_iter_mut().zip(&rows).for_each(render);
+//     builder.merge_into_rstring()
+#[derive(Clone, Debug, Default)]
+pub struct ShardedBuilder {
+    shards: Vec<Shard>,
+}
+
+impl ShardedBuilder {
+    pub fn new(count: usize) -> Self {
+        Self {
+            shards: vec![Shard::new(); count],
+        }
+    }
+
+    pub fn shards_mut(&mut self) -> &mut [Shard] {
+        &mut self.shards
+    }
+
+    pub fn push_shard(&mut self, shard: Shard) {
+        self.shards.push(shard);
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(Shard::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(Shard::is_empty)
+    }
+
+    pub fn merge(self) -> RedString {
+        let mut result = RedString::with_capacity(self.len());
+        for shard in &self.shards {
+            result.push_str(shard.as_str());
+        }
+        result
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn merge_into_rstring(self) -> magnus::RString {
+        let raw_value = unsafe {
+            let raw_value = rb_sys::rb_str_buf_new(self.len().try_into().unwrap());
+            for shard in &self.shards {
+                rb_sys::rb_str_cat(
+                    raw_value,
+                    shard.buf.as_ptr() as *const i8,
+                    shard.len().try_into().unwrap(),
+                );
+            }
+            rb_sys::rb_enc_associate_index(raw_value, rb_sys::rb_utf8_encindex());
+            crate::set_coderange(raw_value, self.shards.iter().all(|s| s.buf.is_ascii()));
+            raw_value
+        };
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+}
+
+impl From<Vec<Shard>> for ShardedBuilder {
+    fn from(shards: Vec<Shard>) -> Self {
+        Self { shards }
+    }
+}
+
+impl FromIterator<Shard> for ShardedBuilder {
+    fn from_iter<I: IntoIterator<Item = Shard>>(iter: I) -> Self {
+        Self {
+            shards: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{Shard, ShardedBuilder};
+
+    fn render(builder: &mut ShardedBuilder) {
+        std::thread::scope(|scope| {
+            for (i, shard) in builder.shards_mut().iter_mut().enumerate() {
+                scope.spawn(move || write!(shard, "<row {i}>").unwrap());
+            }
+        });
+    }
+
+    #[ruby_test]
+    fn test_merge() {
+        let mut builder = ShardedBuilder::new(3);
+        render(&mut builder);
+        builder.push_shard(Shard::new());
+        assert_eq!(builder.len(), 21);
+        assert_eq!(builder.merge().as_str(), "<row 0><row 1><row 2>");
+
+        let collected: ShardedBuilder = ["a", "b"]
+            .iter()
+            .map(|s| {
+                let mut shard = Shard::new();
+                shard.push_str(s);
+                shard
+            })
+            .collect();
+        assert_eq!(collected.merge().as_str(), "ab");
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_merge_into_rstring() {
+        let mut builder = ShardedBuilder::new(2);
+        render(&mut builder);
+        builder.shards_mut()[1].push('é');
+        let rstring = builder.merge_into_rstring();
+        assert_eq!(rstring.to_string().unwrap(), "<row 0><row 1>é");
+    }
+}
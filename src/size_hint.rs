@@ -0,0 +1,157 @@
+use crate::{RedBytes, RedString};
+
+// How many bytes a value takes once written, so a buffer can be reserved
+// once up front instead of doubling its way there. Exact where that's cheap
+// to work out, otherwise an upper bound.
+pub trait SizeHint {
+    fn size_hint(&self) -> usize;
+}
+
+impl SizeHint for str {
+    fn size_hint(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizeHint for String {
+    fn size_hint(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizeHint for RedString {
+    fn size_hint(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizeHint for RedBytes {
+    fn size_hint(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizeHint for char {
+    fn size_hint(&self) -> usize {
+        self.len_utf8()
+    }
+}
+
+impl<T: SizeHint + ?Sized> SizeHint for &T {
+    fn size_hint(&self) -> usize {
+        (**self).size_hint()
+    }
+}
+
+// Everything concatenated.
+impl<T: SizeHint> SizeHint for [T] {
+    fn size_hint(&self) -> usize {
+        self.iter().map(T::size_hint).sum()
+    }
+}
+
+impl<A: SizeHint, B: SizeHint> SizeHint for (A, B) {
+    fn size_hint(&self) -> usize {
+        self.0.size_hint() + self.1.size_hint()
+    }
+}
+
+// `items` joined with `separator`, as written by `RedString::push_joined`.
+pub struct Joined<'a, T> {
+    pub items: &'a [T],
+    pub separator: &'a str,
+}
+
+impl<T: SizeHint> SizeHint for Joined<'_, T> {
+    fn size_hint(&self) -> usize {
+        self.items.size_hint() + self.separator.len() * self.items.len().saturating_sub(1)
+    }
+}
+
+// `push_html_escaped` output if every byte needed the longest entity,
+// `&quot;`.
+pub struct HtmlEscaped<'a>(pub &'a str);
+
+impl SizeHint for HtmlEscaped<'_> {
+    fn size_hint(&self) -> usize {
+        self.0.len() * 6
+    }
+}
+
+// Base64 encoding of `len` bytes, padded to a multiple of 4 or not.
+pub struct Base64 {
+    pub len: usize,
+    pub padded: bool,
+}
+
+impl SizeHint for Base64 {
+    fn size_hint(&self) -> usize {
+        if self.padded {
+            self.len.div_ceil(3) * 4
+        } else {
+            (self.len * 4).div_ceil(3)
+        }
+    }
+}
+
+impl RedString {
+    pub fn with_capacity_for<T: SizeHint + ?Sized>(value: &T) -> Self {
+        Self::with_capacity(value.size_hint())
+    }
+
+    pub fn reserve_for<T: SizeHint + ?Sized>(&mut self, value: &T) {
+        self.reserve(value.size_hint());
+    }
+
+    pub fn push_joined<T: AsRef<str> + SizeHint>(&mut self, items: &[T], separator: &str) {
+        self.reserve_for(&Joined { items, separator });
+        for (idx, item) in items.iter().enumerate() {
+            if idx > 0 {
+                self.push_str(separator);
+            }
+            self.push_str(item.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{Base64, HtmlEscaped, Joined, SizeHint};
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_hints() {
+        assert_eq!(["ab", "cde"].size_hint(), 5);
+        assert_eq!(("é", 'x').size_hint(), 3);
+        let items = ["a", "bb", "ccc"];
+        assert_eq!(
+            Joined {
+                items: &items,
+                separator: ", "
+            }
+            .size_hint(),
+            10
+        );
+        assert_eq!(HtmlEscaped("<a>").size_hint(), 18);
+        for (len, padded, unpadded) in [(0, 0, 0), (1, 4, 2), (2, 4, 3), (3, 4, 4), (4, 8, 6)] {
+            assert_eq!(Base64 { len, padded: true }.size_hint(), padded);
+            assert_eq!(Base64 { len, padded: false }.size_hint(), unpadded);
+        }
+    }
+
+    #[ruby_test]
+    fn test_single_allocation() {
+        let items = ["alpha", "beta", "gamma"];
+        let mut s = RedString::new();
+        s.push_joined(&items, ", ");
+        assert_eq!(s.as_str(), "alpha, beta, gamma");
+        assert_eq!(s.capacity(), s.len());
+
+        let mut s = RedString::with_capacity_for(&HtmlEscaped("<b>"));
+        let capacity = s.capacity();
+        s.push_html_escaped("<b>");
+        assert_eq!(s.capacity(), capacity);
+    }
+}
@@ -0,0 +1,101 @@
+use std::ops::{Deref, DerefMut};
+
+use magnus::rb_sys::FromRawValue;
+
+use crate::{Pinned, RedString};
+
+// A string that keeps being written after parts of it were handed to Ruby,
+// e.g. for progress callbacks. The first `snapshot` after a change copies
+// the contents into a frozen Ruby string; every snapshot is a new string
+// made with `rb_str_new_shared`, so until the next change they all share
+// that one buffer, and Ruby only copies it if a caller modifies theirs.
+//
+// Any mutable access, through `DerefMut`, counts as a change.
+pub struct SnapshotBuilder {
+    buf: RedString,
+    base: Option<Pinned<magnus::RString>>,
+}
+
+impl SnapshotBuilder {
+    pub fn new() -> Self {
+        Self::from_buf(RedString::new())
+    }
+
+    pub fn from_buf(buf: RedString) -> Self {
+        Self { buf, base: None }
+    }
+
+    pub fn snapshot(&mut self) -> magnus::RString {
+        let buf = &self.buf;
+        let base = self
+            .base
+            .get_or_insert_with(|| Pinned::new(buf.clone_to_frozen_rstring()));
+        let raw_value = unsafe { rb_sys::rb_str_new_shared(base.as_raw()) };
+        magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap()
+    }
+
+    pub fn into_inner(self) -> RedString {
+        self.buf
+    }
+}
+
+impl Default for SnapshotBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for SnapshotBuilder {
+    type Target = RedString;
+
+    fn deref(&self) -> &RedString {
+        &self.buf
+    }
+}
+
+impl DerefMut for SnapshotBuilder {
+    fn deref_mut(&mut self) -> &mut RedString {
+        self.base = None;
+        &mut self.buf
+    }
+}
+
+impl std::fmt::Write for SnapshotBuilder {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use magnus::prelude::*;
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::SnapshotBuilder;
+
+    #[ruby_test]
+    fn test_snapshots_share_until_changed() {
+        let mut out = SnapshotBuilder::new();
+        out.push_str(&"progress ".repeat(10));
+
+        let a = out.snapshot();
+        let b = out.snapshot();
+        assert!(!a.is_frozen());
+        assert_eq!(unsafe { a.as_slice().as_ptr() }, unsafe {
+            b.as_slice().as_ptr()
+        });
+
+        // Ruby copies on write, leaving the other snapshot alone.
+        let _: magnus::Value = a.funcall("<<", ("!",)).unwrap();
+        assert_eq!(b.to_string().unwrap(), "progress ".repeat(10));
+
+        out.push_str("done");
+        let c = out.snapshot();
+        assert_ne!(unsafe { c.as_slice().as_ptr() }, unsafe {
+            b.as_slice().as_ptr()
+        });
+        assert!(c.to_string().unwrap().ends_with("done"));
+        assert_eq!(out.into_inner().len(), 94);
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::RedString;
+
+const CHUNK: usize = 64 * 1024;
+
+// Builds output that may be too big to keep in Ruby's heap. Up to
+// `threshold` bytes are buffered in memory; past that, what's buffered is
+// appended to an anonymous temp file and the buffer starts over, so memory
+// use stays bounded however much is written. Big results are meant to be
+// streamed out:
+//
+//     if out.len() <= LIMIT {
+//         out.into_rstring()
+//     } else {
+//         out.write_to_io(io)
+//     }
+//
+// Writing through `fmt::Write` can't report I/O errors, so the first one is
+// kept and returned by the handoff instead.
+pub struct SpillBuilder {
+    buf: RedString,
+    threshold: usize,
+    file: Option<File>,
+    spilled: usize,
+    ascii: bool,
+    error: Option<io::Error>,
+}
+
+impl SpillBuilder {
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            buf: RedString::new(),
+            threshold,
+            file: None,
+            spilled: 0,
+            ascii: true,
+            error: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spilled + self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    pub fn push_str(&mut self, s: &str) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.ascii &= s.is_ascii();
+        if self.buf.len() + s.len() <= self.threshold {
+            self.buf.push_str(s);
+            return Ok(());
+        }
+
+        if self.file.is_none() {
+            self.file = Some(spill_file()?);
+        }
+        let file = self.file.as_mut().unwrap();
+        file.write_all(self.buf.as_bytes())?;
+        self.spilled += self.buf.len();
+        self.buf.clear();
+        if s.len() > self.threshold {
+            file.write_all(s.as_bytes())?;
+            self.spilled += s.len();
+        } else {
+            self.buf.push_str(s);
+        }
+        Ok(())
+    }
+
+    // Reads the spilled part back in chunks, each ending on a char boundary,
+    // then the buffered tail.
+    #[cfg_attr(not(feature = "magnus"), allow(dead_code))]
+    fn for_each_chunk<F>(mut self, mut f: F) -> io::Result<()>
+    where
+        F: FnMut(&str) -> io::Result<()>,
+    {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        if let Some(mut file) = self.file.take() {
+            file.rewind()?;
+            let mut chunk = vec![0; CHUNK];
+            let mut filled = 0;
+            loop {
+                let read = file.read(&mut chunk[filled..])?;
+                if read == 0 {
+                    break;
+                }
+                filled += read;
+                // Only we wrote the file, so the bytes are valid UTF-8 apart
+                // from a char cut off at the end of the chunk.
+                let valid = match std::str::from_utf8(&chunk[..filled]) {
+                    Ok(s) => s.len(),
+                    Err(err) => err.valid_up_to(),
+                };
+                f(unsafe { std::str::from_utf8_unchecked(&chunk[..valid]) })?;
+                chunk.copy_within(valid..filled, 0);
+                filled -= valid;
+            }
+        }
+        f(self.buf.as_str())
+    }
+
+    #[cfg(feature = "magnus")]
+    pub fn write_to_io(self, io: magnus::Value) -> Result<usize, magnus::Error> {
+        let mut written = 0;
+        let mut result = Ok(());
+        let io_result = self.for_each_chunk(|chunk| {
+            if !chunk.is_empty() {
+                match crate::ruby_io::write_str_to_io(io, chunk) {
+                    Ok(n) => written += n,
+                    Err(err) => {
+                        result = Err(err);
+                        return Err(io::Error::other("write to Ruby IO failed"));
+                    }
+                }
+            }
+            Ok(())
+        });
+        result?;
+        io_result.map_err(io_error)?;
+        Ok(written)
+    }
+
+    // Reads everything back into one string of the exact size, so only call
+    // this once `len` is known to be reasonable.
+    #[cfg(feature = "magnus")]
+    pub fn into_rstring(self) -> Result<magnus::RString, magnus::Error> {
+        use magnus::rb_sys::FromRawValue;
+
+        let ascii = self.ascii;
+        let raw_value = unsafe { rb_sys::rb_str_buf_new(self.len().try_into().unwrap()) };
+        self.for_each_chunk(|chunk| {
+            unsafe {
+                rb_sys::rb_str_cat(
+                    raw_value,
+                    chunk.as_ptr() as *const i8,
+                    chunk.len().try_into().unwrap(),
+                );
+            }
+            Ok(())
+        })
+        .map_err(io_error)?;
+        unsafe {
+            rb_sys::rb_enc_associate_index(raw_value, rb_sys::rb_utf8_encindex());
+            crate::set_coderange(raw_value, ascii);
+        }
+        Ok(magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap())
+    }
+}
+
+impl std::fmt::Write for SpillBuilder {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s).map_err(|err| {
+            self.error.get_or_insert(err);
+            std::fmt::Error
+        })
+    }
+}
+
+// Unlinked as soon as it's created, so the space is reclaimed when the file
+// is closed, even if the process dies first.
+fn spill_file() -> io::Result<File> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let path = std::env::temp_dir().join(format!(
+        "redrs-spill-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    std::fs::remove_file(&path)?;
+    Ok(file)
+}
+
+#[cfg(feature = "magnus")]
+fn io_error(err: io::Error) -> magnus::Error {
+    magnus::Error::new(magnus::exception::io_error(), err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::SpillBuilder;
+
+    fn fill(out: &mut SpillBuilder) -> String {
+        let mut expected = String::new();
+        for i in 0..2000 {
+            let line = format!("línea {}\n", i);
+            out.write_str(&line).unwrap();
+            expected.push_str(&line);
+        }
+        out.write_str(&"é".repeat(40_000)).unwrap();
+        expected.push_str(&"é".repeat(40_000));
+        expected
+    }
+
+    #[ruby_test]
+    fn test_stays_in_memory() {
+        let mut out = SpillBuilder::new(1024);
+        write!(out, "small").unwrap();
+        assert!(!out.is_spilled());
+        assert_eq!(out.len(), 5);
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_into_rstring() {
+        let mut out = SpillBuilder::new(1000);
+        let expected = fill(&mut out);
+        assert!(out.is_spilled());
+        assert_eq!(out.len(), expected.len());
+        let rstring = out.into_rstring().unwrap();
+        assert_eq!(rstring.to_string().unwrap(), expected);
+    }
+
+    #[cfg(feature = "magnus")]
+    #[ruby_test]
+    fn test_write_to_io() {
+        use magnus::{eval, prelude::*, RString};
+
+        let io: magnus::Value = eval("require 'stringio'; StringIO.new").unwrap();
+        let mut out = SpillBuilder::new(1000);
+        let expected = fill(&mut out);
+        assert_eq!(out.write_to_io(io).unwrap(), expected.len());
+        let written: RString = io.funcall("string", ()).unwrap();
+        assert_eq!(written.to_string().unwrap(), expected);
+    }
+}
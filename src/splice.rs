@@ -0,0 +1,137 @@
+use std::ops::{Bound, RangeBounds};
+
+use crate::{RedBytes, RedString, RedVec};
+
+impl RedBytes {
+    // Replaces `range` with `replacement`, moving the tail at most once and
+    // reallocating at most once. Panics if `range` is out of bounds.
+    pub fn splice<R>(&mut self, range: R, replacement: &[u8])
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = resolve(range, self.len());
+        splice_vec(&mut self.buf, start, end, replacement);
+    }
+
+    // Same as `splice`, but hands back the bytes that were replaced.
+    pub fn splice_out<R>(&mut self, range: R, replacement: &[u8]) -> RedBytes
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = resolve(range, self.len());
+        let removed = RedBytes::from_slice(&self.buf[start..end]);
+        splice_vec(&mut self.buf, start, end, replacement);
+        removed
+    }
+}
+
+impl RedString {
+    // Panics if `range` is out of bounds or doesn't fall on char
+    // boundaries.
+    pub fn splice<R>(&mut self, range: R, replacement: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.resolve_char_range(range);
+        splice_vec(&mut self.buf, start, end, replacement.as_bytes());
+    }
+
+    pub fn splice_out<R>(&mut self, range: R, replacement: &str) -> RedString
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = self.resolve_char_range(range);
+        let removed = RedString::from_str(&self[start..end]);
+        splice_vec(&mut self.buf, start, end, replacement.as_bytes());
+        removed
+    }
+
+    pub(crate) fn resolve_char_range<R>(&self, range: R) -> (usize, usize)
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = resolve(range, self.len());
+        assert!(
+            self.is_char_boundary(start) && self.is_char_boundary(end),
+            "splice range {}..{} is not on char boundaries",
+            start,
+            end
+        );
+        (start, end)
+    }
+}
+
+fn resolve<R>(range: R, len: usize) -> (usize, usize)
+where
+    R: RangeBounds<usize>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end + 1,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end && end <= len,
+        "splice range {}..{} out of bounds for length {}",
+        start,
+        end,
+        len
+    );
+    (start, end)
+}
+
+fn splice_vec(buf: &mut RedVec<u8>, start: usize, end: usize, replacement: &[u8]) {
+    let len = buf.len();
+    let new_len = len - (end - start) + replacement.len();
+    crate::growth::reserve(buf, new_len.saturating_sub(len));
+    unsafe {
+        let ptr = buf.as_mut_ptr();
+        std::ptr::copy(ptr.add(end), ptr.add(start + replacement.len()), len - end);
+        std::ptr::copy_nonoverlapping(replacement.as_ptr(), ptr.add(start), replacement.len());
+        buf.set_len(new_len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::{RedBytes, RedString};
+
+    #[ruby_test]
+    fn test_splice_bytes() {
+        let mut b = RedBytes::from_slice(b"\x00\x01\x02\x03\x04");
+        b.splice(1..3, b"\xaa\xbb\xcc\xdd");
+        assert_eq!(b.as_slice(), b"\x00\xaa\xbb\xcc\xdd\x03\x04");
+        b.splice(..5, b"");
+        assert_eq!(b.as_slice(), b"\x03\x04");
+        b.splice(2.., b"end");
+        assert_eq!(b.as_slice(), b"\x03\x04end");
+
+        let removed = b.splice_out(0..=1, b"-");
+        assert_eq!(removed.as_slice(), b"\x03\x04");
+        assert_eq!(b.as_slice(), b"-end");
+    }
+
+    #[ruby_test]
+    fn test_splice_str() {
+        let mut s = RedString::from_str("héllo world");
+        let removed = s.splice_out(..6, "goodbye");
+        assert_eq!(removed.as_str(), "héllo");
+        assert_eq!(s.as_str(), "goodbye world");
+        s.splice(7..7, ",");
+        assert_eq!(s.as_str(), "goodbye, world");
+    }
+
+    #[ruby_test]
+    #[should_panic]
+    fn test_splice_char_boundary() {
+        let mut s = RedString::from_str("héllo");
+        s.splice(2..3, "e");
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    RwLock,
+};
+
+// Counters are process-wide and shared by every container using
+// `RubyAllocator`.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static FREED: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+static HOOK: RwLock<Option<&'static dyn AllocHook>> = RwLock::new(None);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    pub allocated: usize,
+    pub freed: usize,
+    pub live: usize,
+    pub peak: usize,
+    pub allocations: usize,
+    pub deallocations: usize,
+    pub reallocations: usize,
+}
+
+// Called synchronously from inside the allocator, so implementations must
+// not allocate through `RubyAllocator` themselves.
+pub trait AllocHook: Sync {
+    fn on_alloc(&self, _size: usize) {}
+    fn on_dealloc(&self, _size: usize) {}
+    fn on_realloc(&self, _old_size: usize, _new_size: usize) {}
+}
+
+pub fn alloc_stats() -> AllocStats {
+    AllocStats {
+        allocated: ALLOCATED.load(Ordering::Relaxed),
+        freed: FREED.load(Ordering::Relaxed),
+        live: LIVE.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+        reallocations: REALLOCATIONS.load(Ordering::Relaxed),
+    }
+}
+
+// Resets the cumulative counters; `live` keeps tracking memory that is still
+// allocated, and `peak` restarts from it.
+pub fn reset_alloc_stats() {
+    ALLOCATED.store(0, Ordering::Relaxed);
+    FREED.store(0, Ordering::Relaxed);
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    DEALLOCATIONS.store(0, Ordering::Relaxed);
+    REALLOCATIONS.store(0, Ordering::Relaxed);
+    PEAK.store(LIVE.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+pub fn set_alloc_hook(hook: Option<&'static dyn AllocHook>) {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = hook;
+}
+
+#[cfg(feature = "magnus")]
+pub fn alloc_stats_to_rhash() -> Result<magnus::RHash, magnus::Error> {
+    let stats = alloc_stats();
+    let hash = magnus::RHash::new();
+    hash.aset(magnus::Symbol::new("allocated"), stats.allocated)?;
+    hash.aset(magnus::Symbol::new("freed"), stats.freed)?;
+    hash.aset(magnus::Symbol::new("live"), stats.live)?;
+    hash.aset(magnus::Symbol::new("peak"), stats.peak)?;
+    hash.aset(magnus::Symbol::new("allocations"), stats.allocations)?;
+    hash.aset(magnus::Symbol::new("deallocations"), stats.deallocations)?;
+    hash.aset(magnus::Symbol::new("reallocations"), stats.reallocations)?;
+    Ok(hash)
+}
+
+fn with_hook(f: impl FnOnce(&dyn AllocHook)) {
+    if let Some(hook) = *HOOK.read().unwrap_or_else(|e| e.into_inner()) {
+        f(hook);
+    }
+}
+
+fn add_live(size: usize) {
+    let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+pub(crate) fn record_alloc(size: usize) {
+    ALLOCATED.fetch_add(size, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    add_live(size);
+    with_hook(|hook| hook.on_alloc(size));
+}
+
+pub(crate) fn record_dealloc(size: usize) {
+    FREED.fetch_add(size, Ordering::Relaxed);
+    DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    LIVE.fetch_sub(size, Ordering::Relaxed);
+    with_hook(|hook| hook.on_dealloc(size));
+}
+
+pub(crate) fn record_realloc(old_size: usize, new_size: usize) {
+    REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    if new_size > old_size {
+        ALLOCATED.fetch_add(new_size - old_size, Ordering::Relaxed);
+        add_live(new_size - old_size);
+    } else {
+        FREED.fetch_add(old_size - new_size, Ordering::Relaxed);
+        LIVE.fetch_sub(old_size - new_size, Ordering::Relaxed);
+    }
+    with_hook(|hook| hook.on_realloc(old_size, new_size));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{alloc_stats, set_alloc_hook, AllocHook};
+    use crate::RedString;
+
+    // The counters are global and tests run in parallel, so only check
+    // relative changes that can't go backwards.
+    #[ruby_test]
+    fn test_alloc_stats() {
+        let before = alloc_stats();
+        let mut s = RedString::with_capacity(16);
+        s.push_str(&"x".repeat(64));
+        drop(s);
+        let after = alloc_stats();
+
+        assert!(after.allocations > before.allocations);
+        assert!(after.reallocations > before.reallocations);
+        assert!(after.deallocations > before.deallocations);
+        assert!(after.allocated >= before.allocated + 64);
+        assert!(after.peak >= 64);
+    }
+
+    struct CountingHook(AtomicUsize);
+
+    impl AllocHook for CountingHook {
+        fn on_alloc(&self, size: usize) {
+            self.0.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    static HOOK: CountingHook = CountingHook(AtomicUsize::new(0));
+
+    #[ruby_test]
+    fn test_alloc_hook() {
+        set_alloc_hook(Some(&HOOK));
+        let s = RedString::with_capacity(32);
+        set_alloc_hook(None);
+        drop(s);
+        assert!(HOOK.0.load(Ordering::Relaxed) >= 32);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::RedString;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    // `offset` counts bytes from the start of the stream.
+    InvalidSequence { offset: usize },
+    Incomplete { offset: usize },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidSequence { offset } => {
+                write!(f, "invalid byte sequence at byte {}", offset)
+            }
+            DecodeError::Incomplete { offset } => {
+                write!(f, "incomplete byte sequence at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// Appends UTF-8 that arrives in arbitrary chunks, holding back a char split
+// across chunk boundaries until the rest of it shows up.
+pub struct StreamingDecoder {
+    string: RedString,
+    pending: [u8; 4],
+    pending_len: usize,
+    consumed: usize,
+}
+
+impl StreamingDecoder {
+    pub fn new() -> Self {
+        Self::with_string(RedString::new())
+    }
+
+    pub fn with_string(string: RedString) -> Self {
+        Self {
+            string,
+            pending: [0; 4],
+            pending_len: 0,
+            consumed: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.string.as_str()
+    }
+
+    pub fn pending(&self) -> &[u8] {
+        &self.pending[..self.pending_len]
+    }
+
+    // On error everything before the invalid sequence has been appended and
+    // the rest of `bytes` is dropped.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), DecodeError> {
+        let bytes = match self.complete_pending(bytes)? {
+            Some(rest) => rest,
+            None => return Ok(()),
+        };
+
+        match std::str::from_utf8(bytes) {
+            Ok(s) => {
+                self.string.push_str(s);
+                self.consumed += bytes.len();
+                Ok(())
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                self.string
+                    .push_str(unsafe { std::str::from_utf8_unchecked(&bytes[..valid]) });
+                if e.error_len().is_some() {
+                    self.consumed += valid;
+                    return Err(DecodeError::InvalidSequence {
+                        offset: self.consumed,
+                    });
+                }
+                let tail = &bytes[valid..];
+                self.pending[..tail.len()].copy_from_slice(tail);
+                self.pending_len = tail.len();
+                self.consumed += bytes.len();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<RedString, DecodeError> {
+        if self.pending_len > 0 {
+            return Err(DecodeError::Incomplete {
+                offset: self.consumed - self.pending_len,
+            });
+        }
+        Ok(self.string)
+    }
+
+    // Returns the bytes left after finishing the pending char, or `None` if
+    // they were all needed and the char is still incomplete.
+    fn complete_pending<'b>(&mut self, bytes: &'b [u8]) -> Result<Option<&'b [u8]>, DecodeError> {
+        if self.pending_len == 0 {
+            return Ok(Some(bytes));
+        }
+
+        let old_len = self.pending_len;
+        let take = (4 - old_len).min(bytes.len());
+        let mut buf = self.pending;
+        buf[old_len..old_len + take].copy_from_slice(&bytes[..take]);
+        let buf = &buf[..old_len + take];
+
+        let (valid, error_len) = match std::str::from_utf8(buf) {
+            Ok(s) => (s.len(), None),
+            Err(e) => (e.valid_up_to(), e.error_len()),
+        };
+        if valid == 0 {
+            if error_len.is_some() {
+                self.pending_len = 0;
+                return Err(DecodeError::InvalidSequence {
+                    offset: self.consumed - old_len,
+                });
+            }
+            self.pending[..buf.len()].copy_from_slice(buf);
+            self.pending_len = buf.len();
+            self.consumed += take;
+            return Ok(None);
+        }
+
+        let ch = unsafe { std::str::from_utf8_unchecked(&buf[..valid]) }
+            .chars()
+            .next()
+            .unwrap();
+        self.string.push(ch);
+        let used = ch.len_utf8() - old_len;
+        self.pending_len = 0;
+        self.consumed += used;
+        Ok(Some(&bytes[used..]))
+    }
+}
+
+impl Default for StreamingDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::io::Write for StreamingDecoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.push_bytes(buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{DecodeError, StreamingDecoder};
+
+    #[ruby_test]
+    fn test_split_chars() {
+        let input = "añ日🎉b".as_bytes();
+        for chunk in 1..input.len() {
+            let mut decoder = StreamingDecoder::new();
+            for piece in input.chunks(chunk) {
+                decoder.push_bytes(piece).unwrap();
+            }
+            assert_eq!(decoder.finish().unwrap().as_str(), "añ日🎉b");
+        }
+    }
+
+    #[ruby_test]
+    fn test_invalid() {
+        let mut decoder = StreamingDecoder::new();
+        decoder.push_bytes(b"ab\xE6").unwrap();
+        assert_eq!(decoder.pending(), b"\xE6");
+        assert_eq!(
+            decoder.push_bytes(b"x"),
+            Err(DecodeError::InvalidSequence { offset: 2 })
+        );
+        assert_eq!(decoder.as_str(), "ab");
+
+        let mut decoder = StreamingDecoder::new();
+        assert_eq!(
+            decoder.push_bytes(b"ok\xFFno"),
+            Err(DecodeError::InvalidSequence { offset: 2 })
+        );
+        assert_eq!(decoder.as_str(), "ok");
+    }
+
+    #[ruby_test]
+    fn test_incomplete() {
+        let mut decoder = StreamingDecoder::new();
+        decoder.push_bytes(b"abc\xF0\x9F").unwrap();
+        assert_eq!(
+            decoder.finish().err(),
+            Some(DecodeError::Incomplete { offset: 3 })
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::ffi::CString;
+
+use magnus::rb_sys::FromRawValue;
+use rb_sys::rb_econv_result_t;
+
+use crate::RedString;
+
+// Values of `RUBY_ECONV_INVALID_REPLACE` and `RUBY_ECONV_UNDEF_REPLACE`. They
+// are only exposed as enum members with aliased values, which bindgen doesn't
+// turn into usable constants.
+const ECONV_INVALID_REPLACE: i32 = 0x0000_0002;
+const ECONV_UNDEF_REPLACE: i32 = 0x0000_0020;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Fallback {
+    #[default]
+    Error,
+    Replace,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TranscodeOptions {
+    pub invalid: Fallback,
+    pub undefined: Fallback,
+}
+
+impl TranscodeOptions {
+    fn ecflags(&self) -> i32 {
+        let mut flags = 0;
+        if self.invalid == Fallback::Replace {
+            flags |= ECONV_INVALID_REPLACE;
+        }
+        if self.undefined == Fallback::Replace {
+            flags |= ECONV_UNDEF_REPLACE;
+        }
+        flags
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscodeError {
+    UnknownEncoding(String),
+    ConverterNotFound(String),
+    InvalidByteSequence,
+    UndefinedConversion,
+}
+
+impl std::fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranscodeError::UnknownEncoding(name) => write!(f, "unknown encoding name - {}", name),
+            TranscodeError::ConverterNotFound(name) => {
+                write!(f, "code converter not found (UTF-8 to {})", name)
+            }
+            TranscodeError::InvalidByteSequence => write!(f, "invalid byte sequence"),
+            TranscodeError::UndefinedConversion => {
+                write!(f, "character cannot be represented in the target encoding")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TranscodeError {}
+
+impl RedString {
+    pub fn into_rstring_transcoded(
+        self,
+        dst_encoding: &str,
+        options: TranscodeOptions,
+    ) -> Result<magnus::RString, TranscodeError> {
+        let name = CString::new(dst_encoding)
+            .map_err(|_| TranscodeError::UnknownEncoding(dst_encoding.to_owned()))?;
+
+        let encindex = unsafe { rb_sys::rb_enc_find_index(name.as_ptr()) };
+        if encindex < 0 {
+            return Err(TranscodeError::UnknownEncoding(dst_encoding.to_owned()));
+        }
+        // Already UTF-8, under any of its names; there is no converter for that.
+        if encindex == unsafe { rb_sys::rb_utf8_encindex() } {
+            return Ok(self.into_rstring());
+        }
+
+        // Allocated first, so nothing can raise between opening the converter
+        // and the protected loop below.
+        let raw_value = unsafe { rb_sys::rb_str_buf_new(self.len().try_into().unwrap()) };
+        let ec =
+            unsafe { rb_sys::rb_econv_open(c"UTF-8".as_ptr(), name.as_ptr(), options.ecflags()) };
+        if ec.is_null() {
+            return Err(TranscodeError::ConverterNotFound(dst_encoding.to_owned()));
+        }
+
+        let mut conversion = Conversion {
+            ec,
+            src: self.buf.as_ptr(),
+            src_end: unsafe { self.buf.as_ptr().add(self.len()) },
+            dst: raw_value,
+            result: Ok(()),
+        };
+        let mut state = 0;
+        unsafe {
+            rb_sys::rb_protect(
+                Some(Conversion::run),
+                &mut conversion as *mut Conversion as rb_sys::VALUE,
+                &mut state,
+            );
+            rb_sys::rb_econv_close(ec);
+        }
+        if state != 0 {
+            drop(self);
+            drop(name);
+            unsafe { rb_sys::rb_jump_tag(state) };
+        }
+        conversion.result?;
+
+        unsafe { rb_sys::rb_enc_associate_index(raw_value, encindex) };
+        Ok(magnus::RString::from_value(unsafe { magnus::Value::from_raw(raw_value) }).unwrap())
+    }
+}
+
+// The conversion loop, run under `rb_protect` because appending to the
+// result can raise `NoMemoryError`, which would skip `rb_econv_close`.
+struct Conversion {
+    ec: *mut rb_sys::rb_econv_t,
+    src: *const u8,
+    src_end: *const u8,
+    dst: rb_sys::VALUE,
+    result: Result<(), TranscodeError>,
+}
+
+impl Conversion {
+    unsafe extern "C" fn run(arg: rb_sys::VALUE) -> rb_sys::VALUE {
+        let conversion = &mut *(arg as *mut Conversion);
+        conversion.result = conversion.convert();
+        rb_sys::Qnil as rb_sys::VALUE
+    }
+
+    unsafe fn convert(&mut self) -> Result<(), TranscodeError> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            let mut dst = chunk.as_mut_ptr();
+            let res = rb_sys::rb_econv_convert(
+                self.ec,
+                &mut self.src,
+                self.src_end,
+                &mut dst,
+                dst.add(chunk.len()),
+                0,
+            );
+            let written = dst.offset_from(chunk.as_ptr());
+            rb_sys::rb_str_cat(self.dst, chunk.as_ptr() as *const i8, written as _);
+
+            match res {
+                rb_econv_result_t::econv_destination_buffer_full
+                | rb_econv_result_t::econv_after_output => continue,
+                rb_econv_result_t::econv_finished
+                | rb_econv_result_t::econv_source_buffer_empty => return Ok(()),
+                rb_econv_result_t::econv_invalid_byte_sequence
+                | rb_econv_result_t::econv_incomplete_input => {
+                    return Err(TranscodeError::InvalidByteSequence)
+                }
+                rb_econv_result_t::econv_undefined_conversion => {
+                    return Err(TranscodeError::UndefinedConversion)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use super::{Fallback, TranscodeError, TranscodeOptions};
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_transcode() {
+        let s = RedString::from_str("café");
+        let rstring = s
+            .into_rstring_transcoded("ISO-8859-1", TranscodeOptions::default())
+            .unwrap();
+        assert_eq!(unsafe { rstring.as_slice() }, &[99, 97, 102, 233]);
+    }
+
+    #[ruby_test]
+    fn test_transcode_to_utf8() {
+        for name in ["UTF-8", "utf-8"] {
+            let rstring = RedString::from_str("café")
+                .into_rstring_transcoded(name, TranscodeOptions::default())
+                .unwrap();
+            assert_eq!(rstring.to_string().unwrap(), "café");
+        }
+    }
+
+    #[ruby_test]
+    fn test_transcode_undefined() {
+        let s = RedString::from_str("日本");
+        let err = s
+            .into_rstring_transcoded("Windows-1252", TranscodeOptions::default())
+            .unwrap_err();
+        assert_eq!(err, TranscodeError::UndefinedConversion);
+
+        let options = TranscodeOptions {
+            undefined: Fallback::Replace,
+            ..Default::default()
+        };
+        let s = RedString::from_str("a日b");
+        let rstring = s.into_rstring_transcoded("Windows-1252", options).unwrap();
+        assert_eq!(unsafe { rstring.as_slice() }, b"a?b");
+    }
+
+    #[ruby_test]
+    fn test_transcode_unknown_encoding() {
+        let s = RedString::from_str("abc");
+        let err = s
+            .into_rstring_transcoded("NOT-AN-ENCODING", TranscodeOptions::default())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            TranscodeError::UnknownEncoding("NOT-AN-ENCODING".to_owned())
+        );
+    }
+}
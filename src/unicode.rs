@@ -0,0 +1,63 @@
+use unicode_normalization::{is_nfc_quick, is_nfd_quick, IsNormalized, UnicodeNormalization};
+
+use crate::RedString;
+
+impl RedString {
+    pub fn normalize_nfc(&mut self) {
+        if is_nfc_quick(self.chars()) == IsNormalized::Yes {
+            return;
+        }
+        let mut result = Self::with_capacity(self.len());
+        result.push_str_nfc(self);
+        *self = result;
+    }
+
+    pub fn normalize_nfd(&mut self) {
+        if is_nfd_quick(self.chars()) == IsNormalized::Yes {
+            return;
+        }
+        let mut result = Self::with_capacity(self.len());
+        result.push_str_nfd(self);
+        *self = result;
+    }
+
+    pub fn push_str_nfc(&mut self, s: &str) {
+        for c in s.nfc() {
+            self.push(c);
+        }
+    }
+
+    pub fn push_str_nfd(&mut self, s: &str) {
+        for c in s.nfd() {
+            self.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_normalize_nfc() {
+        let mut s = RedString::from_str("cafe\u{301}");
+        s.normalize_nfc();
+        assert_eq!(s.as_str(), "caf\u{e9}");
+    }
+
+    #[ruby_test]
+    fn test_normalize_nfd() {
+        let mut s = RedString::from_str("caf\u{e9}");
+        s.normalize_nfd();
+        assert_eq!(s.as_str(), "cafe\u{301}");
+    }
+
+    #[ruby_test]
+    fn test_push_str_nfc() {
+        let mut s = RedString::from_str("a");
+        s.push_str_nfc("e\u{301}");
+        assert_eq!(s.as_str(), "a\u{e9}");
+    }
+}
@@ -0,0 +1,98 @@
+use unicode_width::UnicodeWidthChar;
+
+use crate::RedString;
+
+impl RedString {
+    // Terminal columns, counting East Asian wide characters and emoji as two
+    // and combining marks as zero.
+    pub fn width(&self) -> usize {
+        char_widths(self).map(|(_, _, width)| width).sum()
+    }
+
+    // Cuts the string so that it, plus `ellipsis`, fits in `cols` columns,
+    // never splitting a wide character or a joined emoji. Nothing changes if
+    // it already fits, and an ellipsis wider than `cols` is left out.
+    // Returns whether anything was cut.
+    pub fn truncate_to_width(&mut self, cols: usize, ellipsis: &str) -> bool {
+        if self.width() <= cols {
+            return false;
+        }
+        let ellipsis_width: usize = char_widths(ellipsis).map(|(_, _, width)| width).sum();
+        let (budget, ellipsis) = match cols.checked_sub(ellipsis_width) {
+            Some(budget) => (budget, ellipsis),
+            None => (cols, ""),
+        };
+
+        let mut used = 0;
+        let mut end = 0;
+        for (idx, c, width) in char_widths(self) {
+            if used + width > budget {
+                break;
+            }
+            used += width;
+            end = idx + c.len_utf8();
+        }
+        self.buf.truncate(end);
+        self.push_str(ellipsis);
+        true
+    }
+}
+
+// `unicode-width` measures every char on its own, which overcounts emoji:
+// chars joined by a ZWJ form one glyph, and VS16 after a narrow char asks
+// for its two-column emoji presentation.
+fn char_widths(s: &str) -> impl Iterator<Item = (usize, char, usize)> + '_ {
+    let mut joined = false;
+    let mut prev_width = 0;
+    s.char_indices().map(move |(idx, c)| {
+        let width = if c == '\u{200d}' {
+            joined = true;
+            0
+        } else if joined {
+            joined = false;
+            0
+        } else if c == '\u{fe0f}' {
+            (prev_width == 1) as usize
+        } else {
+            c.width().unwrap_or(0)
+        };
+        prev_width = width;
+        (idx, c, width)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rb_sys_test_helpers::ruby_test;
+
+    use crate::RedString;
+
+    #[ruby_test]
+    fn test_width() {
+        assert_eq!(RedString::from_str("abc").width(), 3);
+        assert_eq!(RedString::from_str("日本").width(), 4);
+        assert_eq!(RedString::from_str("e\u{301}").width(), 1);
+        assert_eq!(RedString::from_str("👨\u{200d}👩\u{200d}👧").width(), 2);
+        assert_eq!(RedString::from_str("\u{2764}\u{fe0f}").width(), 2);
+    }
+
+    #[ruby_test]
+    fn test_truncate_to_width() {
+        let mut s = RedString::from_str("short");
+        assert!(!s.truncate_to_width(5, "…"));
+        assert_eq!(s.as_str(), "short");
+
+        let mut s = RedString::from_str("日本語テキスト");
+        assert!(s.truncate_to_width(6, "…"));
+        assert_eq!(s.as_str(), "日本…");
+        assert!(s.width() <= 6);
+
+        let mut s = RedString::from_str("a👨\u{200d}👩b");
+        assert!(s.truncate_to_width(3, ""));
+        assert_eq!(s.as_str(), "a👨\u{200d}👩");
+
+        let mut s = RedString::from_str("abcdef");
+        assert!(s.truncate_to_width(2, "..."));
+        assert_eq!(s.as_str(), "ab");
+    }
+}